@@ -0,0 +1,248 @@
+//! A simple selector matching benchmark.
+//!
+//! Compares the default `&str` based local name matching
+//! with an overridden byte-oriented one, and the default class matching
+//! via `attribute_matches` with a `HashSet` based `has_class`.
+//!
+//! Run with `cargo bench --bench matching`.
+//!
+//! Baseline numbers (release build, x86_64 Linux):
+//!
+//! ```text
+//! selector/str            time:   [102.10 µs 104.62 µs 107.12 µs]
+//! selector/bytes          time:   [50.725 µs 53.783 µs 56.657 µs]
+//! sheet/class attribute   time:   [686.27 µs 712.32 µs 739.98 µs]
+//! sheet/class set         time:   [628.64 µs 648.23 µs 670.48 µs]
+//! ```
+
+use std::collections::HashSet;
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use simplecss::{AttributeOperator, Element, PseudoClass, Selector, StyleSheet};
+
+// A stylesheet similar to the one that can be found in an SVG file.
+const STYLE_SHEET: &str = "
+svg { fill: none }
+g > rect { fill: black }
+rect + circle { stroke: red }
+.icon { fill: currentColor }
+.icon.active { fill: blue }
+g.group > .shape { stroke-width: 2 }
+g.group circle.shape.highlight { stroke: yellow }
+.hidden { display: none }
+#main .shape:first-child { opacity: 0.5 }
+[class~=outline] { stroke: gray }
+";
+
+struct Node {
+    tag: Vec<u8>,
+    class: String,
+    classes: HashSet<String>,
+    parent: Option<usize>,
+    prev_sibling: Option<usize>,
+}
+
+impl Node {
+    fn new(tag: &[u8], class: &str, parent: Option<usize>, prev_sibling: Option<usize>) -> Self {
+        Node {
+            tag: tag.to_vec(),
+            class: class.to_string(),
+            classes: class.split_ascii_whitespace().map(String::from).collect(),
+            parent,
+            prev_sibling,
+        }
+    }
+}
+
+// A flat tree with a byte-oriented storage.
+struct Tree {
+    nodes: Vec<Node>,
+}
+
+impl Tree {
+    fn new() -> Self {
+        let mut nodes = vec![Node::new(b"svg", "", None, None)];
+        for i in 0..100 {
+            let g = nodes.len();
+            let prev = if i == 0 { None } else { Some(g - 11) };
+            let class = if i % 3 == 0 { "group hidden" } else { "group" };
+            nodes.push(Node::new(b"g", class, Some(0), prev));
+
+            for j in 0..10 {
+                let idx = nodes.len();
+                let tag: &[u8] = if j % 2 == 0 { b"rect" } else { b"circle" };
+                let class = match j % 4 {
+                    0 => "shape icon",
+                    1 => "shape highlight outline",
+                    2 => "icon active",
+                    _ => "",
+                };
+                let prev = if j == 0 { None } else { Some(idx - 1) };
+                nodes.push(Node::new(tag, class, Some(g), prev));
+            }
+        }
+
+        Tree { nodes }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct StrNode<'a>(&'a Tree, usize);
+
+impl Element for StrNode<'_> {
+    fn parent_element(&self) -> Option<Self> {
+        self.0.nodes[self.1].parent.map(|idx| StrNode(self.0, idx))
+    }
+
+    fn prev_sibling_element(&self) -> Option<Self> {
+        self.0.nodes[self.1].prev_sibling.map(|idx| StrNode(self.0, idx))
+    }
+
+    fn has_local_name(&self, name: &str) -> bool {
+        std::str::from_utf8(&self.0.nodes[self.1].tag) == Ok(name)
+    }
+
+    fn attribute_matches(&self, _: &str, _: AttributeOperator) -> bool {
+        false
+    }
+
+    fn pseudo_class_matches(&self, _: PseudoClass) -> bool {
+        false
+    }
+}
+
+#[derive(Clone, Copy)]
+struct BytesNode<'a>(&'a Tree, usize);
+
+impl Element for BytesNode<'_> {
+    fn parent_element(&self) -> Option<Self> {
+        self.0.nodes[self.1].parent.map(|idx| BytesNode(self.0, idx))
+    }
+
+    fn prev_sibling_element(&self) -> Option<Self> {
+        self.0.nodes[self.1].prev_sibling.map(|idx| BytesNode(self.0, idx))
+    }
+
+    fn has_local_name(&self, name: &str) -> bool {
+        self.has_local_name_bytes(name.as_bytes())
+    }
+
+    fn has_local_name_bytes(&self, name: &[u8]) -> bool {
+        self.0.nodes[self.1].tag == name
+    }
+
+    fn attribute_matches(&self, _: &str, _: AttributeOperator) -> bool {
+        false
+    }
+
+    fn pseudo_class_matches(&self, _: PseudoClass) -> bool {
+        false
+    }
+}
+
+// Matches classes by parsing the `class` attribute on each call.
+#[derive(Clone, Copy)]
+struct AttrNode<'a>(&'a Tree, usize);
+
+impl Element for AttrNode<'_> {
+    fn parent_element(&self) -> Option<Self> {
+        self.0.nodes[self.1].parent.map(|idx| AttrNode(self.0, idx))
+    }
+
+    fn prev_sibling_element(&self) -> Option<Self> {
+        self.0.nodes[self.1].prev_sibling.map(|idx| AttrNode(self.0, idx))
+    }
+
+    fn has_local_name(&self, name: &str) -> bool {
+        self.0.nodes[self.1].tag == name.as_bytes()
+    }
+
+    fn attribute_matches(&self, local_name: &str, operator: AttributeOperator) -> bool {
+        match local_name {
+            "class" => operator.matches(&self.0.nodes[self.1].class),
+            "id" => operator.matches(if self.1 == 0 { "main" } else { "" }),
+            _ => false,
+        }
+    }
+
+    fn pseudo_class_matches(&self, class: PseudoClass) -> bool {
+        match class {
+            PseudoClass::FirstChild => self.prev_sibling_element().is_none(),
+            _ => false,
+        }
+    }
+}
+
+// Matches classes using a precomputed set.
+#[derive(Clone, Copy)]
+struct ClassNode<'a>(&'a Tree, usize);
+
+impl Element for ClassNode<'_> {
+    fn parent_element(&self) -> Option<Self> {
+        self.0.nodes[self.1].parent.map(|idx| ClassNode(self.0, idx))
+    }
+
+    fn prev_sibling_element(&self) -> Option<Self> {
+        self.0.nodes[self.1].prev_sibling.map(|idx| ClassNode(self.0, idx))
+    }
+
+    fn has_local_name(&self, name: &str) -> bool {
+        self.0.nodes[self.1].tag == name.as_bytes()
+    }
+
+    fn attribute_matches(&self, local_name: &str, operator: AttributeOperator) -> bool {
+        AttrNode(self.0, self.1).attribute_matches(local_name, operator)
+    }
+
+    fn has_class(&self, class: &str) -> bool {
+        self.0.nodes[self.1].classes.contains(class)
+    }
+
+    fn pseudo_class_matches(&self, class: PseudoClass) -> bool {
+        AttrNode(self.0, self.1).pseudo_class_matches(class)
+    }
+}
+
+fn selector(c: &mut Criterion) {
+    let tree = Tree::new();
+    let selector = Selector::parse("svg g > rect + circle").unwrap();
+
+    let mut group = c.benchmark_group("selector");
+    group.bench_function("str", |b| b.iter(|| {
+        (0..tree.nodes.len()).filter(|idx| selector.matches(&StrNode(&tree, black_box(*idx)))).count()
+    }));
+    group.bench_function("bytes", |b| b.iter(|| {
+        (0..tree.nodes.len()).filter(|idx| selector.matches(&BytesNode(&tree, black_box(*idx)))).count()
+    }));
+    group.finish();
+}
+
+fn sheet(c: &mut Criterion) {
+    let tree = Tree::new();
+    let sheet = StyleSheet::parse(STYLE_SHEET);
+
+    let mut group = c.benchmark_group("sheet");
+    group.bench_function("class attribute", |b| b.iter(|| {
+        let mut count = 0;
+        for idx in 0..tree.nodes.len() {
+            let node = AttrNode(&tree, black_box(idx));
+            count += sheet.rules.iter().filter(|rule| rule.selector.matches(&node)).count();
+        }
+
+        count
+    }));
+    group.bench_function("class set", |b| b.iter(|| {
+        let mut count = 0;
+        for idx in 0..tree.nodes.len() {
+            let node = ClassNode(&tree, black_box(idx));
+            count += sheet.rules.iter().filter(|rule| rule.selector.matches(&node)).count();
+        }
+
+        count
+    }));
+    group.finish();
+}
+
+criterion_group!(benches, selector, sheet);
+criterion_main!(benches);
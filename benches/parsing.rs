@@ -0,0 +1,54 @@
+//! A style sheet parsing benchmark.
+//!
+//! Uses a generated, Bootstrap-sized style sheet with a lot of grouped selectors.
+//!
+//! Run with `cargo bench --bench parsing`.
+//!
+//! Baseline numbers (release build, x86_64 Linux):
+//!
+//! ```text
+//! parse/grouped selectors time:   [6.5717 ms 6.7904 ms 7.0203 ms]
+//!                         thrpt:  [41.381 MiB/s 42.782 MiB/s 44.206 MiB/s]
+//! ```
+
+use std::fmt::Write;
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use simplecss::StyleSheet;
+
+// Generates about 200KB of CSS with ~6000 rules.
+fn generate() -> String {
+    let mut text = String::new();
+    for i in 0..600 {
+        for j in 0..10 {
+            if j != 0 {
+                text.push_str(", ");
+            }
+
+            write!(text, ".btn-{}-{}:hover > .icon", i, j).unwrap();
+        }
+
+        text.push_str(" {\n");
+        for j in 0..8 {
+            writeln!(text, "  prop-{}: {}px solid #{:06x};", j, i, i * j).unwrap();
+        }
+        text.push_str("}\n");
+    }
+
+    text
+}
+
+fn parse(c: &mut Criterion) {
+    let text = generate();
+
+    let mut group = c.benchmark_group("parse");
+    group.throughput(Throughput::Bytes(text.len() as u64));
+    group.bench_function("grouped selectors", |b| {
+        b.iter(|| StyleSheet::parse(black_box(&text)))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, parse);
+criterion_main!(benches);
@@ -0,0 +1,51 @@
+use alloc::collections::BTreeMap;
+
+use crate::{Declaration, Specificity};
+
+/// Resolves declarations that win the cascade.
+///
+/// `matching` contains declarations with their selectors specificity in the source order,
+/// usually collected from rules that match an element.
+///
+/// An `!important` declaration overrides a normal one regardless of specificity.
+/// Otherwise, a declaration with a higher specificity wins.
+/// Declarations with the same importance and specificity are overridden by the later ones.
+///
+/// Rules origins are not taken into account. Use `StyleSheet::declarations_for` for this.
+///
+/// Returns the winning declarations by their names.
+///
+/// # Example
+///
+/// ```
+/// use simplecss::{cascade, StyleSheet};
+///
+/// let sheet = StyleSheet::parse("#a { color:red; fill:red } p { color:green !important; fill:green }");
+/// let declarations = cascade(sheet.rules.iter().flat_map(|rule| {
+///     let spec = rule.selector.specificity();
+///     rule.declarations.iter().map(move |d| (spec, d))
+/// }));
+///
+/// assert_eq!(declarations["color"].value, "green");
+/// assert_eq!(declarations["fill"].value, "red");
+/// ```
+pub fn cascade<'a, I>(matching: I) -> BTreeMap<&'a str, &'a Declaration<'a>>
+    where I: IntoIterator<Item = (Specificity, &'a Declaration<'a>)>
+{
+    let mut winners: BTreeMap<&str, ((bool, Specificity), &Declaration)> = BTreeMap::new();
+    for (spec, declaration) in matching {
+        let priority = (declaration.important, spec);
+        match winners.get_mut(declaration.name.as_ref()) {
+            Some(prev) => {
+                if prev.0 <= priority {
+                    *prev = (priority, declaration);
+                }
+            }
+            None => {
+                winners.insert(declaration.name.as_ref(), (priority, declaration));
+            }
+        }
+    }
+
+    winners.into_iter().map(|(name, (_, declaration))| (name, declaration)).collect()
+}
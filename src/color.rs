@@ -0,0 +1,292 @@
+use crate::stream::Stream;
+
+
+/// An RGBA color.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[allow(missing_docs)]
+pub struct Color {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+    pub alpha: u8,
+}
+
+impl Color {
+    /// Constructs a new `Color` from RGBA values.
+    #[inline]
+    pub fn new_rgba(red: u8, green: u8, blue: u8, alpha: u8) -> Self {
+        Color { red, green, blue, alpha }
+    }
+
+    /// Parses a color from a string.
+    ///
+    /// Supports `#rgb`, `#rgba`, `#rrggbb`, `#rrggbbaa`, `rgb()`, `rgba()`,
+    /// named colors and `transparent`. Case-insensitive.
+    ///
+    /// Returns `None` when the string is not a color.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use simplecss::Color;
+    ///
+    /// assert_eq!(Color::parse("#f00"), Some(Color::new_rgba(255, 0, 0, 255)));
+    /// assert_eq!(Color::parse("rgba(255, 0, 0, 0.5)"), Some(Color::new_rgba(255, 0, 0, 128)));
+    /// assert_eq!(Color::parse("Red"), Some(Color::new_rgba(255, 0, 0, 255)));
+    /// assert_eq!(Color::parse("5px"), None);
+    /// ```
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+
+        if let Some(hex) = text.strip_prefix('#') {
+            return parse_hex(hex);
+        }
+
+        if let Some(open) = text.find('(') {
+            let name = &text[..open];
+            if !text.ends_with(')') {
+                return None;
+            }
+
+            let args = &text[open + 1..text.len() - 1];
+            if name.eq_ignore_ascii_case("rgb") || name.eq_ignore_ascii_case("rgba") {
+                return parse_rgb(args);
+            }
+
+            return None;
+        }
+
+        if text.eq_ignore_ascii_case("transparent") {
+            return Some(Color::new_rgba(0, 0, 0, 0));
+        }
+
+        COLORS.iter()
+            .find(|(name, ..)| text.eq_ignore_ascii_case(name))
+            .map(|&(_, r, g, b)| Color::new_rgba(r, g, b, 255))
+    }
+}
+
+fn parse_hex(hex: &str) -> Option<Color> {
+    if !hex.bytes().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let digit = |i: usize| u8::from_str_radix(&hex[i..i + 1], 16).ok();
+    let short = |i: usize| digit(i).map(|n| n * 17);
+    let long = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+
+    match hex.len() {
+        3 => Some(Color::new_rgba(short(0)?, short(1)?, short(2)?, 255)),
+        4 => Some(Color::new_rgba(short(0)?, short(1)?, short(2)?, short(3)?)),
+        6 => Some(Color::new_rgba(long(0)?, long(2)?, long(4)?, 255)),
+        8 => Some(Color::new_rgba(long(0)?, long(2)?, long(4)?, long(6)?)),
+        _ => None,
+    }
+}
+
+fn parse_rgb(args: &str) -> Option<Color> {
+    // Both `rgb(1, 2, 3, 0.5)` and `rgb(1 2 3 / 0.5)` are allowed.
+    let mut values = [0u8, 0, 0, 255];
+    let mut count = 0;
+    for (i, arg) in args.split(|c: char| c == ',' || c == '/' || c.is_ascii_whitespace())
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .enumerate()
+    {
+        if i > 3 {
+            return None;
+        }
+
+        let (number, percent) = match arg.strip_suffix('%') {
+            Some(number) => (number, true),
+            None => (arg, false),
+        };
+
+        let number = parse_number(number)?;
+        // Alpha is a number in a 0..1 range, while other channels are in a 0..255 range.
+        let value = if percent {
+            number / 100.0
+        } else if i == 3 {
+            number
+        } else {
+            number / 255.0
+        };
+
+        values[i] = (value.clamp(0.0, 1.0) * 255.0 + 0.5) as u8;
+        count = i + 1;
+    }
+
+    if count < 3 {
+        return None;
+    }
+
+    Some(Color::new_rgba(values[0], values[1], values[2], values[3]))
+}
+
+pub(crate) fn parse_number(text: &str) -> Option<f64> {
+    let mut s = Stream::from(text);
+    s.try_consume_byte(b'-');
+    s.try_consume_byte(b'+');
+    s.skip_bytes(|c| c.is_ascii_digit());
+    if s.curr_byte() == Ok(b'.') {
+        s.advance(1);
+        s.skip_bytes(|c| c.is_ascii_digit());
+    }
+
+    if !s.at_end() {
+        return None;
+    }
+
+    text.parse().ok()
+}
+
+static COLORS: &[(&str, u8, u8, u8)] = &[
+    ("aliceblue", 240, 248, 255),
+    ("antiquewhite", 250, 235, 215),
+    ("aqua", 0, 255, 255),
+    ("aquamarine", 127, 255, 212),
+    ("azure", 240, 255, 255),
+    ("beige", 245, 245, 220),
+    ("bisque", 255, 228, 196),
+    ("black", 0, 0, 0),
+    ("blanchedalmond", 255, 235, 205),
+    ("blue", 0, 0, 255),
+    ("blueviolet", 138, 43, 226),
+    ("brown", 165, 42, 42),
+    ("burlywood", 222, 184, 135),
+    ("cadetblue", 95, 158, 160),
+    ("chartreuse", 127, 255, 0),
+    ("chocolate", 210, 105, 30),
+    ("coral", 255, 127, 80),
+    ("cornflowerblue", 100, 149, 237),
+    ("cornsilk", 255, 248, 220),
+    ("crimson", 220, 20, 60),
+    ("cyan", 0, 255, 255),
+    ("darkblue", 0, 0, 139),
+    ("darkcyan", 0, 139, 139),
+    ("darkgoldenrod", 184, 134, 11),
+    ("darkgray", 169, 169, 169),
+    ("darkgreen", 0, 100, 0),
+    ("darkgrey", 169, 169, 169),
+    ("darkkhaki", 189, 183, 107),
+    ("darkmagenta", 139, 0, 139),
+    ("darkolivegreen", 85, 107, 47),
+    ("darkorange", 255, 140, 0),
+    ("darkorchid", 153, 50, 204),
+    ("darkred", 139, 0, 0),
+    ("darksalmon", 233, 150, 122),
+    ("darkseagreen", 143, 188, 143),
+    ("darkslateblue", 72, 61, 139),
+    ("darkslategray", 47, 79, 79),
+    ("darkslategrey", 47, 79, 79),
+    ("darkturquoise", 0, 206, 209),
+    ("darkviolet", 148, 0, 211),
+    ("deeppink", 255, 20, 147),
+    ("deepskyblue", 0, 191, 255),
+    ("dimgray", 105, 105, 105),
+    ("dimgrey", 105, 105, 105),
+    ("dodgerblue", 30, 144, 255),
+    ("firebrick", 178, 34, 34),
+    ("floralwhite", 255, 250, 240),
+    ("forestgreen", 34, 139, 34),
+    ("fuchsia", 255, 0, 255),
+    ("gainsboro", 220, 220, 220),
+    ("ghostwhite", 248, 248, 255),
+    ("gold", 255, 215, 0),
+    ("goldenrod", 218, 165, 32),
+    ("gray", 128, 128, 128),
+    ("green", 0, 128, 0),
+    ("greenyellow", 173, 255, 47),
+    ("grey", 128, 128, 128),
+    ("honeydew", 240, 255, 240),
+    ("hotpink", 255, 105, 180),
+    ("indianred", 205, 92, 92),
+    ("indigo", 75, 0, 130),
+    ("ivory", 255, 255, 240),
+    ("khaki", 240, 230, 140),
+    ("lavender", 230, 230, 250),
+    ("lavenderblush", 255, 240, 245),
+    ("lawngreen", 124, 252, 0),
+    ("lemonchiffon", 255, 250, 205),
+    ("lightblue", 173, 216, 230),
+    ("lightcoral", 240, 128, 128),
+    ("lightcyan", 224, 255, 255),
+    ("lightgoldenrodyellow", 250, 250, 210),
+    ("lightgray", 211, 211, 211),
+    ("lightgreen", 144, 238, 144),
+    ("lightgrey", 211, 211, 211),
+    ("lightpink", 255, 182, 193),
+    ("lightsalmon", 255, 160, 122),
+    ("lightseagreen", 32, 178, 170),
+    ("lightskyblue", 135, 206, 250),
+    ("lightslategray", 119, 136, 153),
+    ("lightslategrey", 119, 136, 153),
+    ("lightsteelblue", 176, 196, 222),
+    ("lightyellow", 255, 255, 224),
+    ("lime", 0, 255, 0),
+    ("limegreen", 50, 205, 50),
+    ("linen", 250, 240, 230),
+    ("magenta", 255, 0, 255),
+    ("maroon", 128, 0, 0),
+    ("mediumaquamarine", 102, 205, 170),
+    ("mediumblue", 0, 0, 205),
+    ("mediumorchid", 186, 85, 211),
+    ("mediumpurple", 147, 112, 219),
+    ("mediumseagreen", 60, 179, 113),
+    ("mediumslateblue", 123, 104, 238),
+    ("mediumspringgreen", 0, 250, 154),
+    ("mediumturquoise", 72, 209, 204),
+    ("mediumvioletred", 199, 21, 133),
+    ("midnightblue", 25, 25, 112),
+    ("mintcream", 245, 255, 250),
+    ("mistyrose", 255, 228, 225),
+    ("moccasin", 255, 228, 181),
+    ("navajowhite", 255, 222, 173),
+    ("navy", 0, 0, 128),
+    ("oldlace", 253, 245, 230),
+    ("olive", 128, 128, 0),
+    ("olivedrab", 107, 142, 35),
+    ("orange", 255, 165, 0),
+    ("orangered", 255, 69, 0),
+    ("orchid", 218, 112, 214),
+    ("palegoldenrod", 238, 232, 170),
+    ("palegreen", 152, 251, 152),
+    ("paleturquoise", 175, 238, 238),
+    ("palevioletred", 219, 112, 147),
+    ("papayawhip", 255, 239, 213),
+    ("peachpuff", 255, 218, 185),
+    ("peru", 205, 133, 63),
+    ("pink", 255, 192, 203),
+    ("plum", 221, 160, 221),
+    ("powderblue", 176, 224, 230),
+    ("purple", 128, 0, 128),
+    ("rebeccapurple", 102, 51, 153),
+    ("red", 255, 0, 0),
+    ("rosybrown", 188, 143, 143),
+    ("royalblue", 65, 105, 225),
+    ("saddlebrown", 139, 69, 19),
+    ("salmon", 250, 128, 114),
+    ("sandybrown", 244, 164, 96),
+    ("seagreen", 46, 139, 87),
+    ("seashell", 255, 245, 238),
+    ("sienna", 160, 82, 45),
+    ("silver", 192, 192, 192),
+    ("skyblue", 135, 206, 235),
+    ("slateblue", 106, 90, 205),
+    ("slategray", 112, 128, 144),
+    ("slategrey", 112, 128, 144),
+    ("snow", 255, 250, 250),
+    ("springgreen", 0, 255, 127),
+    ("steelblue", 70, 130, 180),
+    ("tan", 210, 180, 140),
+    ("teal", 0, 128, 128),
+    ("thistle", 216, 191, 216),
+    ("tomato", 255, 99, 71),
+    ("turquoise", 64, 224, 208),
+    ("violet", 238, 130, 238),
+    ("wheat", 245, 222, 179),
+    ("white", 255, 255, 255),
+    ("whitesmoke", 245, 245, 245),
+    ("yellow", 255, 255, 0),
+    ("yellowgreen", 154, 205, 50),
+];
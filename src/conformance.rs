@@ -0,0 +1,141 @@
+//! A conformance helper for `Element` implementations.
+//!
+//! # Example
+//!
+//! ```
+//! # struct Node;
+//! # impl simplecss::Element for Node {
+//! #     fn parent_element(&self) -> Option<Self> { None }
+//! #     fn prev_sibling_element(&self) -> Option<Self> { None }
+//! #     fn has_local_name(&self, _: &str) -> bool { false }
+//! #     fn attribute_matches(&self, _: &str, _: simplecss::AttributeOperator) -> bool { false }
+//! #     fn pseudo_class_matches(&self, _: simplecss::PseudoClass) -> bool { false }
+//! # }
+//! # fn find_by_id(_: &str) -> Node { Node }
+//! // Somewhere in your tests.
+//! fn element_conformance() {
+//!     // Build the `TREE` using your own DOM and then:
+//!     simplecss::conformance::check_element_conformance(|id| find_by_id(id));
+//! }
+//! ```
+
+use crate::{Element, Selector};
+
+/// A tree that is expected by `check_element_conformance`.
+///
+/// Every element has a unique `id` attribute. Whitespaces are not significant.
+pub const TREE: &str = "\
+<root id='root'>
+    <div id='div1' class='note warn'>
+        <p id='p1' lang='en-US'/>
+        <p id='p2' class='warn'/>
+        <p id='p3' title='text'/>
+        <p id='p4'/>
+    </div>
+    <section id='section1'>
+        <div id='div2'>
+            <span id='span1'/>
+        </div>
+    </section>
+</root>
+";
+
+/// Checks that an `Element` implementation is correct.
+///
+/// The caller must build the `TREE` using its own DOM implementation
+/// and provide a function that returns an element by its `id` attribute.
+///
+/// The `:first-child` pseudo-class must be supported by the implementation.
+/// Other dynamic pseudo-classes are not checked.
+///
+/// # Panics
+///
+/// Panics on the first failed check with a description of what went wrong.
+pub fn check_element_conformance<E, F>(find: F)
+    where E: Element, F: Fn(&str) -> E
+{
+    let check = |selector: &str, id: &str, expected: bool| {
+        let selector = Selector::parse(selector)
+            .unwrap_or_else(|| panic!("'{}' is not a valid selector", selector));
+
+        assert_eq!(selector.matches(&find(id)), expected,
+                   "'{}' must {}match #{}",
+                   selector, if expected { "" } else { "not " }, id);
+    };
+
+    // Type and universal.
+    check("root", "root", true);
+    check("p", "p1", true);
+    check("div", "p1", false);
+    check("*", "span1", true);
+
+    // Descendant.
+    check("root p", "p1", true);
+    check("root span", "span1", true);
+    check("section span", "span1", true);
+    check("section p", "p1", false);
+    check("div div span", "span1", false);
+
+    // Child.
+    check("div > p", "p2", true);
+    check("root > div", "div1", true);
+    check("root > div", "div2", false);
+    check("section > span", "span1", false);
+    check("root > section > div > span", "span1", true);
+
+    // Adjacent sibling.
+    check("p + p", "p2", true);
+    check("p + p", "p1", false);
+    check("#p2 + p", "p3", true);
+    check("#p1 + p", "p3", false);
+    check("div + section", "section1", true);
+
+    // Attributes.
+    check("[lang]", "p1", true);
+    check("[lang]", "p2", false);
+    check("[title=text]", "p3", true);
+    check("[lang|=en]", "p1", true);
+    check(".warn", "p2", true);
+    check(".warn", "div1", true);
+    check(".note.warn", "div1", true);
+    check(".note", "p2", false);
+    check("#p4", "p4", true);
+    check("div.warn > p.warn", "p2", true);
+
+    // Pseudo-classes.
+    check("p:first-child", "p1", true);
+    check("p:first-child", "p2", false);
+    check("div:first-child", "div1", true);
+    check("section:first-child", "section1", false);
+
+    // `:nth-child`.
+    check("p:nth-child(2)", "p2", true);
+    check("p:nth-child(odd)", "p3", true);
+    check("p:nth-child(odd)", "p4", false);
+    check("p:nth-child(even)", "p4", true);
+    check("p:nth-child(-n+2)", "p3", false);
+    check("section:nth-child(2)", "section1", true);
+
+    // `:scope`.
+    check(":scope", "root", true);
+    check(":scope", "div1", false);
+    check(":scope > div", "div1", true);
+    check(":scope > div", "div2", false);
+
+    // `:root`.
+    check(":root", "root", true);
+    check(":root", "div1", false);
+    check(":root > div", "div1", true);
+
+    // `:lang`.
+    check(":lang(en)", "p1", true);
+    check(":lang(en-US)", "p1", true);
+    check(":lang(fr)", "p1", false);
+    check(":lang(en)", "p2", false);
+
+    // `:not`.
+    check("p:not(.warn)", "p1", true);
+    check("p:not(.warn)", "p2", false);
+    check("div > :not(p)", "p1", false);
+    check(":not(div)", "span1", true);
+}
@@ -0,0 +1,196 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::color::parse_number;
+use crate::stream::Stream;
+use crate::{consume_term, split_args, Color};
+
+
+/// A gradient.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Gradient {
+    /// A gradient kind.
+    pub kind: GradientKind,
+    /// Color stops. Contains at least two stops.
+    pub stops: Vec<GradientStop>,
+}
+
+/// A gradient kind.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GradientKind {
+    /// `linear-gradient()`
+    Linear(GradientDirection),
+    /// `radial-gradient()`
+    ///
+    /// Shape, size and position are not parsed.
+    Radial,
+}
+
+/// A linear gradient direction.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GradientDirection {
+    /// An angle in degrees.
+    ///
+    /// `to top` is `0deg`, `to right` is `90deg` and so on.
+    /// Default is `180deg`, aka `to bottom`.
+    Angle(f64),
+    /// A corner, like `to top left`.
+    ///
+    /// The actual angle depends on the gradient box size.
+    Corner {
+        /// Indicates a top or a bottom corner.
+        top: bool,
+        /// Indicates a left or a right corner.
+        left: bool,
+    },
+}
+
+/// A gradient color stop.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct GradientStop {
+    /// A stop color.
+    pub color: Color,
+    /// A stop offset in a 0..1 range, if set.
+    pub offset: Option<f64>,
+}
+
+impl Gradient {
+    /// Parses a gradient from a string.
+    ///
+    /// Supports `linear-gradient()` and `radial-gradient()`. Case-insensitive.
+    /// Stop offsets must be set in percentages.
+    ///
+    /// Returns `None` when the string is not a gradient or the gradient is invalid.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use simplecss::{Color, Gradient, GradientDirection, GradientKind};
+    ///
+    /// let g = Gradient::parse("linear-gradient(90deg, red, blue 50%)").unwrap();
+    /// assert_eq!(g.kind, GradientKind::Linear(GradientDirection::Angle(90.0)));
+    /// assert_eq!(g.stops[1].color, Color::new_rgba(0, 0, 255, 255));
+    /// assert_eq!(g.stops[1].offset, Some(0.5));
+    /// assert_eq!(Gradient::parse("red"), None);
+    /// ```
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        let open = text.find('(')?;
+        if !text.ends_with(')') {
+            return None;
+        }
+
+        let name = &text[..open];
+        let mut args = split_args(&text[open + 1..text.len() - 1]).into_iter().peekable();
+
+        let kind = if name.eq_ignore_ascii_case("linear-gradient") {
+            let direction = args.peek().and_then(|arg| parse_direction(arg));
+            if direction.is_some() {
+                args.next();
+            }
+
+            GradientKind::Linear(direction.unwrap_or(GradientDirection::Angle(180.0)))
+        } else if name.eq_ignore_ascii_case("radial-gradient") {
+            // Skip shape, size and position.
+            if args.peek().map(|arg| parse_stops(arg).is_none()) == Some(true) {
+                args.next();
+            }
+
+            GradientKind::Radial
+        } else {
+            return None;
+        };
+
+        let mut stops = Vec::new();
+        for arg in args {
+            stops.extend(parse_stops(arg)?);
+        }
+
+        if stops.len() < 2 {
+            return None;
+        }
+
+        Some(Gradient { kind, stops })
+    }
+}
+
+fn parse_direction(text: &str) -> Option<GradientDirection> {
+    let mut words = text.split_ascii_whitespace();
+    let first = words.next()?;
+    if !first.eq_ignore_ascii_case("to") {
+        return parse_angle(first).filter(|_| words.next().is_none()).map(GradientDirection::Angle);
+    }
+
+    let mut vertical = None;
+    let mut horizontal = None;
+    for word in words {
+        let word = word.to_ascii_lowercase();
+        match word.as_str() {
+            "top" | "bottom" if vertical.is_none() => vertical = Some(word == "top"),
+            "left" | "right" if horizontal.is_none() => horizontal = Some(word == "left"),
+            _ => return None,
+        }
+    }
+
+    let direction = match (vertical, horizontal) {
+        (Some(top), Some(left)) => GradientDirection::Corner { top, left },
+        (Some(true), None) => GradientDirection::Angle(0.0),
+        (Some(false), None) => GradientDirection::Angle(180.0),
+        (None, Some(true)) => GradientDirection::Angle(270.0),
+        (None, Some(false)) => GradientDirection::Angle(90.0),
+        (None, None) => return None,
+    };
+
+    Some(direction)
+}
+
+fn parse_angle(text: &str) -> Option<f64> {
+    let split = text.find(|c: char| c.is_ascii_alphabetic())?;
+    let number = parse_number(&text[..split])?;
+    let unit = &text[split..];
+    let angle = if unit.eq_ignore_ascii_case("deg") {
+        number
+    } else if unit.eq_ignore_ascii_case("grad") {
+        number * 0.9
+    } else if unit.eq_ignore_ascii_case("rad") {
+        number.to_degrees()
+    } else if unit.eq_ignore_ascii_case("turn") {
+        number * 360.0
+    } else {
+        return None;
+    };
+
+    Some(angle)
+}
+
+// Parses `color [offset [offset]]`. Two offsets produce two stops.
+fn parse_stops(text: &str) -> Option<Vec<GradientStop>> {
+    let mut terms = Vec::new();
+    let mut s = Stream::from(text);
+    while !s.at_end() {
+        s.skip_spaces();
+        let start = s.pos();
+        consume_term(&mut s).ok()?;
+        terms.push(s.slice_back(start));
+    }
+
+    let (color, offsets) = terms.split_first()?;
+    let color = Color::parse(color)?;
+    if offsets.len() > 2 {
+        return None;
+    }
+
+    if offsets.is_empty() {
+        return Some(vec![GradientStop { color, offset: None }]);
+    }
+
+    offsets.iter().map(|offset| {
+        let offset = match offset.strip_suffix('%') {
+            Some(number) => parse_number(number)? / 100.0,
+            None if *offset == "0" => 0.0,
+            None => return None,
+        };
+
+        Some(GradientStop { color, offset: Some(offset) })
+    }).collect()
+}
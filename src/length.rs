@@ -0,0 +1,76 @@
+use crate::color::parse_number;
+use crate::stream::Stream;
+use crate::consume_term;
+
+
+/// A length unit.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[allow(missing_docs)]
+pub enum LengthUnit {
+    /// A unitless number, like `5`.
+    None,
+    Px,
+    Em,
+    Rem,
+    Ex,
+    Pt,
+    Pc,
+    Cm,
+    Mm,
+    In,
+    /// `%`
+    Percent,
+}
+
+/// Parses a length or a percentage.
+///
+/// Units are case-insensitive. Unitless numbers are allowed.
+///
+/// Returns `None` when the string is not a length or has an unsupported unit.
+///
+/// # Example
+///
+/// ```
+/// use simplecss::{parse_length, LengthUnit};
+///
+/// assert_eq!(parse_length("1.5em"), Some((1.5, LengthUnit::Em)));
+/// assert_eq!(parse_length(" 50% "), Some((50.0, LengthUnit::Percent)));
+/// assert_eq!(parse_length("-2"), Some((-2.0, LengthUnit::None)));
+/// assert_eq!(parse_length("5deg"), None);
+/// assert_eq!(parse_length("red"), None);
+/// ```
+pub fn parse_length(text: &str) -> Option<(f64, LengthUnit)> {
+    let text = text.trim();
+
+    // Must be a single term.
+    let mut s = Stream::from(text);
+    consume_term(&mut s).ok()?;
+    if !s.at_end() {
+        return None;
+    }
+
+    let split = text.find(|c: char| c.is_ascii_alphabetic() || c == '%').unwrap_or(text.len());
+    let number = parse_number(&text[..split])?;
+    let unit = &text[split..];
+    let unit = if unit.is_empty() {
+        LengthUnit::None
+    } else if unit == "%" {
+        LengthUnit::Percent
+    } else {
+        const UNITS: &[(&str, LengthUnit)] = &[
+            ("px", LengthUnit::Px),
+            ("em", LengthUnit::Em),
+            ("rem", LengthUnit::Rem),
+            ("ex", LengthUnit::Ex),
+            ("pt", LengthUnit::Pt),
+            ("pc", LengthUnit::Pc),
+            ("cm", LengthUnit::Cm),
+            ("mm", LengthUnit::Mm),
+            ("in", LengthUnit::In),
+        ];
+
+        UNITS.iter().find(|(name, _)| unit.eq_ignore_ascii_case(name))?.1
+    };
+
+    Some((number, unit))
+}
@@ -9,13 +9,15 @@ Since it's very simple we will start with limitations:
 
 ## Limitations
 
-- [At-rules](https://www.w3.org/TR/CSS21/syndata.html#at-rules) are not supported.
+- [At-rules](https://www.w3.org/TR/CSS21/syndata.html#at-rules) are not supported,
+  except `@import`, `@namespace`, `@property`, `@font-face`, `@keyframes` and `@supports`.
   They will be skipped during parsing.
 - Property values are not parsed.
   In CSS like `* { width: 5px }` you will get a `width` property with a `5px` value as a string.
 - CDO/CDC comments are not supported.
 - Parser is case sensitive. All keywords must be lowercase.
-- Unicode escape, like `\26`, is not supported.
+- Escapes, like `\26`, are decoded only in declaration names, `SelectorTokenizer` class and ID tokens
+  and during selector matching. Use `unescape` to decode values and other identifiers.
 
 ## Features
 
@@ -24,22 +26,59 @@ Since it's very simple we will start with limitations:
 - `!important` parsing support.
 - Has a high-level parsers and low-level, zero-allocation tokenizers.
 - No unsafe.
+- `no_std` support via disabling the default `std` feature. Requires `alloc`.
+  Warnings logging can be disabled via the `log` feature.
+- Optional `serde` support via the `serde` feature.
+  Deserialization borrows strings from the input,
+  therefore it works only with formats and inputs that allow borrowing.
 */
 
 #![doc(html_root_url = "https://docs.rs/simplecss/0.2.1")]
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
 #![forbid(unsafe_code)]
 #![warn(missing_docs)]
 
-use std::fmt;
-
-use log::warn;
+extern crate alloc;
+
+use alloc::borrow::Cow;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::iter::FusedIterator;
+use core::ops::Range;
+
+macro_rules! warn {
+    ($handler:expr, $($arg:tt)+) => {
+        ($handler)(format_args!($($arg)+))
+    };
+}
 
+mod cascade;
+mod color;
+pub mod conformance;
+mod gradient;
+mod length;
+mod owned;
 mod selector;
+mod shorthand;
 mod stream;
+mod variables;
 
+pub use cascade::cascade;
+pub use color::Color;
+pub use gradient::{Gradient, GradientDirection, GradientKind, GradientStop};
+pub use length::{parse_length, LengthUnit};
+pub use owned::OwnedStyleSheet;
 pub use selector::*;
-use stream::Stream;
+pub use shorthand::expand_shorthand;
+pub use variables::resolve_variables;
+use stream::{LineIndex, Stream};
 
 
 /// A list of possible errors.
@@ -77,6 +116,69 @@ pub enum Error {
 
     /// An invalid language pseudo-class.
     InvalidLanguagePseudoClass,
+
+    /// An invalid `nth-child` pseudo-class expression.
+    InvalidNthChildPseudoClass,
+
+    /// An unsupported pseudo-class.
+    UnsupportedPseudoClass,
+
+    /// An unsupported at-rule.
+    UnsupportedAtRule,
+
+    /// An unexpected data after a parsed item.
+    UnexpectedData(TextPos),
+
+    /// Rules or selectors are nested deeper than `ParseOptions::max_nesting_depth`.
+    NestingLimitExceeded,
+
+    /// A style sheet is bigger than `ParseOptions::max_input_size`.
+    InputSizeLimitExceeded,
+}
+
+impl Error {
+    /// Checks that parsing can continue after this error.
+    ///
+    /// A recoverable error affects only the current item, i.e. a rule, a declaration or a selector,
+    /// which can be skipped. A non-recoverable one means that the rest of the text cannot be parsed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use simplecss::StyleSheet;
+    ///
+    /// let (_, diagnostics) = StyleSheet::parse_with_diagnostics("p { color:red } a /* { color:red }");
+    /// assert!(!diagnostics[0].1.recoverable());
+    /// ```
+    pub fn recoverable(&self) -> bool {
+        match *self {
+            // The text has ended, so there is nothing to recover to.
+            Error::UnexpectedEndOfStream => false,
+            // An unclosed comment consumes the rest of the text.
+            Error::InvalidComment(_) => false,
+            // Invalid values and idents affect only the current declaration or rule,
+            // which can be skipped up to the next `;` or `}`.
+            Error::InvalidIdent(_) |
+            Error::InvalidValue(_) |
+            Error::InvalidByte { .. } => true,
+            // Selector errors invalidate only the current rule.
+            Error::SelectorMissing |
+            Error::UnexpectedSelector |
+            Error::UnexpectedCombinator |
+            Error::InvalidAttributeSelector |
+            Error::InvalidLanguagePseudoClass |
+            Error::InvalidNthChildPseudoClass |
+            Error::UnsupportedPseudoClass => true,
+            // Unsupported at-rules are skipped as a whole.
+            Error::UnsupportedAtRule => true,
+            // The parsed item itself is valid.
+            Error::UnexpectedData(_) => true,
+            // Too deep rules and selectors are skipped as a whole.
+            Error::NestingLimitExceeded => true,
+            // The rest of the text is ignored.
+            Error::InputSizeLimitExceeded => false,
+        }
+    }
 }
 
 impl fmt::Display for Error {
@@ -113,29 +215,94 @@ impl fmt::Display for Error {
             Error::InvalidLanguagePseudoClass => {
                 write!(f, "invalid language pseudo-class")
             }
+            Error::InvalidNthChildPseudoClass => {
+                write!(f, "invalid nth-child pseudo-class")
+            }
+            Error::UnsupportedPseudoClass => {
+                write!(f, "unsupported pseudo-class")
+            }
+            Error::UnsupportedAtRule => {
+                write!(f, "unsupported at-rule")
+            }
+            Error::UnexpectedData(pos) => {
+                write!(f, "unexpected data at {}", pos)
+            }
+            Error::NestingLimitExceeded => {
+                write!(f, "nesting limit exceeded")
+            }
+            Error::InputSizeLimitExceeded => {
+                write!(f, "input size limit exceeded")
+            }
         }
     }
 }
 
+#[cfg(feature = "std")]
 impl std::error::Error for Error {}
 
 
 /// A position in text.
 ///
 /// Position indicates a row/line and a column in the original text. Starting from 1:1.
+/// Columns are counted in characters (Unicode scalar values) and not in bytes.
+/// Also contains a byte offset in the original text, which is not displayed.
+///
+/// The text is not normalized. Only `\n` starts a new row, so `\r\n` is a single line break,
+/// and a leading BOM is counted as a column of the first row.
 #[derive(Clone, Copy, PartialEq, Debug)]
 #[allow(missing_docs)]
 pub struct TextPos {
     pub row: u32,
     pub col: u32,
+    /// A byte offset in the original text. Starting from 0.
+    pub offset: usize,
 }
 
 impl TextPos {
     /// Constructs a new `TextPos`.
     ///
     /// Should not be invoked manually, but rather via `Stream::gen_text_pos`.
-    pub fn new(row: u32, col: u32) -> TextPos {
-        TextPos { row, col }
+    pub fn new(row: u32, col: u32, offset: usize) -> TextPos {
+        TextPos { row, col, offset }
+    }
+
+    /// Returns the number of rows in the text.
+    ///
+    /// Rows are counted the same way as in `TextPos`, so a trailing `\n` starts an empty row.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use simplecss::TextPos;
+    ///
+    /// assert_eq!(TextPos::line_count(""), 1);
+    /// assert_eq!(TextPos::line_count("a {}\r\nb {}"), 2);
+    /// assert_eq!(TextPos::line_count("a {}\n"), 2);
+    /// ```
+    pub fn line_count(text: &str) -> usize {
+        text.bytes().filter(|c| *c == b'\n').count() + 1
+    }
+
+    /// Returns the row of the text that contains this position, without a line break.
+    ///
+    /// Useful to show the context of an error. Doesn't allocate.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use simplecss::StyleSheet;
+    ///
+    /// let text = "a { color:red }\r\nb > > c { color:red }\r\n";
+    /// let (_, diagnostics) = StyleSheet::parse_with_diagnostics(text);
+    /// assert_eq!(diagnostics[0].0.line_of(text), "b > > c { color:red }");
+    /// ```
+    pub fn line_of<'a>(&self, text: &'a str) -> &'a str {
+        let offset = self.offset.min(text.len());
+        let bytes = text.as_bytes();
+        let start = bytes[..offset].iter().rposition(|c| *c == b'\n').map_or(0, |i| i + 1);
+        let end = bytes[offset..].iter().position(|c| *c == b'\n').map_or(text.len(), |i| offset + i);
+        let line = &text[start..end];
+        line.strip_suffix('\r').unwrap_or(line)
     }
 }
 
@@ -147,132 +314,1878 @@ impl fmt::Display for TextPos {
 
 
 /// A declaration.
-#[derive(Clone, Copy, PartialEq, Debug)]
+///
+/// `name` has escapes decoded, so `\63 olor` is `color`. It's borrowed from the original text
+/// unless it has escapes. `value` is always borrowed, so escapes are preserved.
+/// Use `unescape` when needed.
+///
+/// Declarations are compared and hashed by `name`, `value` and `important` only.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(missing_docs)]
 pub struct Declaration<'a> {
-    pub name: &'a str,
+    #[cfg_attr(feature = "serde", serde(borrow))]
+    pub name: Cow<'a, str>,
     pub value: &'a str,
     pub important: bool,
+    /// The original, untrimmed value.
+    ///
+    /// Unlike `value`, includes surrounding whitespaces and comments,
+    /// but not the `!important` part. Equal to `value` when created via `Declaration::new`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use simplecss::DeclarationTokenizer;
+    ///
+    /// let mut t = DeclarationTokenizer::from("color:  red  /* c */ !important");
+    /// let d = t.next().unwrap();
+    /// assert_eq!(d.value, "red");
+    /// assert_eq!(d.raw_value, "  red  /* c */ ");
+    /// ```
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub raw_value: &'a str,
+}
+
+impl PartialEq for Declaration<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.value == other.value && self.important == other.important
+    }
+}
+
+impl Eq for Declaration<'_> {}
+
+// Must be consistent with `PartialEq`.
+impl core::hash::Hash for Declaration<'_> {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.name.hash(state);
+        self.value.hash(state);
+        self.important.hash(state);
+    }
+}
+
+impl<'a> Declaration<'a> {
+    /// Creates a new declaration.
+    pub fn new(name: &'a str, value: &'a str, important: bool) -> Self {
+        Declaration { name: Cow::Borrowed(name), value, important, raw_value: value }
+    }
+
+    /// Parses a single declaration, like `color: red !important`.
+    ///
+    /// Unlike `DeclarationTokenizer`, which simply stops at invalid data,
+    /// returns an error when anything except whitespaces, comments and `;`
+    /// follows the declaration.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use simplecss::{Declaration, Error, TextPos};
+    ///
+    /// let d = Declaration::parse_single(" color : red !important; ").unwrap();
+    /// assert_eq!(d, Declaration::new("color", "red", true));
+    ///
+    /// let e = Declaration::parse_single("color: red) blue").unwrap_err();
+    /// assert_eq!(e, Error::UnexpectedData(TextPos::new(1, 11, 10)));
+    /// ```
+    pub fn parse_single(text: &'a str) -> Result<Self, Error> {
+        let mut s = Stream::from(text);
+        let declaration = consume_declaration(&mut s)?;
+        if !s.at_end() {
+            return Err(Error::UnexpectedData(s.gen_text_pos()));
+        }
+
+        Ok(declaration)
+    }
+
+    /// Checks that the declaration is a custom property, like `--main-color`.
+    pub fn is_custom_property(&self) -> bool {
+        self.name.starts_with("--")
+    }
+
+    /// Returns a custom property name without the `--` prefix.
+    ///
+    /// Returns `None` when the declaration is not a custom property.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use simplecss::Declaration;
+    ///
+    /// assert_eq!(Declaration::new("--main-color", "red", false).variable_name(), Some("main-color"));
+    /// assert_eq!(Declaration::new("color", "red", false).variable_name(), None);
+    /// ```
+    pub fn variable_name(&self) -> Option<&str> {
+        self.name.strip_prefix("--")
+    }
+
+    /// Parses the declaration value as a color.
+    ///
+    /// See `Color::parse` for details.
+    pub fn as_color(&self) -> Option<Color> {
+        Color::parse(self.value)
+    }
+
+    /// Parses the declaration value as a gradient.
+    ///
+    /// See `Gradient::parse` for details.
+    pub fn as_gradient(&self) -> Option<Gradient> {
+        Gradient::parse(self.value)
+    }
+
+    /// Checks that both declarations values are the same color.
+    ///
+    /// Returns `false` when any of the values is not a color.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use simplecss::Declaration;
+    ///
+    /// let a = Declaration::new("fill", "#f00", false);
+    /// let b = Declaration::new("color", "rgb(255, 0, 0)", false);
+    /// assert!(a.value_color_eq(&b));
+    /// ```
+    pub fn value_color_eq(&self, other: &Declaration) -> bool {
+        match (self.as_color(), other.as_color()) {
+            (Some(c1), Some(c2)) => c1 == c2,
+            _ => false,
+        }
+    }
+
+    /// Splits the declaration value into terms.
+    ///
+    /// See `value_tokens` for details.
+    pub fn value_tokens(&self) -> impl Iterator<Item = &'a str> {
+        value_tokens(self.value)
+    }
+
+    /// Returns a list of functions from the declaration value.
+    ///
+    /// Each function is represented by its name and a list of comma-separated arguments.
+    /// Arguments are trimmed, but not parsed. Nested functions and strings are preserved.
+    /// Non-function terms are ignored.
+    ///
+    /// Parsing will be stopped at the first invalid term.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use simplecss::Declaration;
+    ///
+    /// let d = Declaration::new("transform", "translate(10, 20) rotate(45)", false);
+    /// assert_eq!(d.functions(), vec![("translate", vec!["10", "20"]), ("rotate", vec!["45"])]);
+    /// ```
+    pub fn functions(&self) -> Vec<(&'a str, Vec<&'a str>)> {
+        let mut functions = Vec::new();
+
+        let mut s = Stream::from(self.value);
+        while !s.at_end() {
+            if s.skip_spaces_and_comments().is_err() || s.at_end() {
+                break;
+            }
+
+            let start = s.pos();
+            if consume_term(&mut s).is_err() {
+                break;
+            }
+
+            let term = s.slice_back(start);
+            if term.starts_with(['\'', '"']) || !term.ends_with(')') {
+                continue;
+            }
+
+            if let Some(open) = term.find('(') {
+                functions.push((&term[..open], split_args(&term[open + 1..term.len() - 1])));
+            }
+        }
+
+        functions
+    }
+}
+
+fn split_args(text: &str) -> Vec<&str> {
+    let mut args = Vec::new();
+    if text.trim().is_empty() {
+        return args;
+    }
+
+    // Split only by top-level commas.
+    let mut s = Stream::from(text);
+    let mut start = 0;
+    let mut depth = 0;
+    while !s.at_end() {
+        match s.curr_byte_unchecked() {
+            b'(' => depth += 1,
+            b')' => depth -= 1,
+            b',' if depth == 0 => {
+                args.push(s.slice_back(start).trim());
+                start = s.pos() + 1;
+            }
+            b'\'' | b'"' => {
+                if s.consume_string().is_err() {
+                    break;
+                }
+
+                continue;
+            }
+            _ => {}
+        }
+
+        s.advance(1);
+    }
+
+    s.jump_to_end();
+    args.push(s.slice_back(start).trim());
+    args
+}
+
+/// A style sheet origin.
+///
+/// Cf. https://www.w3.org/TR/css-cascade-3/#cascading-origins.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Origin {
+    /// A user agent style sheet, i.e. default styles.
+    UserAgent,
+    /// A user style sheet.
+    User,
+    /// A document style sheet.
+    Author,
+}
+
+impl Origin {
+    // Precedence from the lowest to the highest.
+    // Origins of important declarations are in the reversed order.
+    fn cascade_level(self, important: bool) -> u8 {
+        match (self, important) {
+            (Origin::UserAgent, false) => 0,
+            (Origin::User, false) => 1,
+            (Origin::Author, false) => 2,
+            (Origin::Author, true) => 3,
+            (Origin::User, true) => 4,
+            (Origin::UserAgent, true) => 5,
+        }
+    }
+}
+
+/// `Origin::Author`
+impl Default for Origin {
+    fn default() -> Self {
+        Origin::Author
+    }
 }
 
 /// A rule.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Rule<'a> {
     /// A rule selector.
+    #[cfg_attr(feature = "serde", serde(borrow))]
     pub selector: Selector<'a>,
     /// A rule declarations.
+    ///
+    /// Rules with grouped selectors, like `a, b { color:red }`, share the same declarations.
+    /// Use `Arc::make_mut` to modify them.
+    #[cfg_attr(feature = "serde", serde(borrow))]
+    pub declarations: Arc<Vec<Declaration<'a>>>,
+    /// A rule origin.
+    ///
+    /// Parsed rules are `Origin::Author` by default. Use `StyleSheet::set_origin` to change it.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub origin: Origin,
+    /// A list of `@supports` conditions this rule is nested in, like `(display: grid)`.
+    ///
+    /// Ordered from the outermost to the innermost one. Conditions are not evaluated
+    /// and stored as is, so the rule should be applied only when all of them are supported.
+    #[cfg_attr(feature = "serde", serde(borrow, default))]
+    pub supports: Vec<&'a str>,
+    /// A rule position in the source order.
+    ///
+    /// Rules added by `parse_more` continue after the largest value in the style sheet,
+    /// so a later rule always has a bigger value.
+    /// Can be used to resolve rules with the same specificity after `rules` were reordered.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub source_order: usize,
+}
+
+impl<'a> Rule<'a> {
+    /// Checks that the rule selector matches the specified element.
+    ///
+    /// A shorthand for `rule.selector.matches(element)`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # struct Node;
+    /// # impl simplecss::Element for Node {
+    /// #     fn parent_element(&self) -> Option<Self> { None }
+    /// #     fn prev_sibling_element(&self) -> Option<Self> { None }
+    /// #     fn has_local_name(&self, name: &str) -> bool { name == "p" }
+    /// #     fn attribute_matches(&self, _: &str, _: simplecss::AttributeOperator) -> bool { false }
+    /// #     fn pseudo_class_matches(&self, _: simplecss::PseudoClass) -> bool { false }
+    /// # }
+    /// use simplecss::StyleSheet;
+    ///
+    /// let sheet = StyleSheet::parse("p { color:red } div { color:green }");
+    /// let rules: Vec<_> = sheet.rules.iter().filter(|r| r.matches(&Node)).collect();
+    /// assert_eq!(rules.len(), 1);
+    /// assert_eq!(rules[0].declarations[0].value, "red");
+    /// ```
+    pub fn matches<E: Element>(&self, element: &E) -> bool {
+        self.selector.matches(element)
+    }
+}
+
+/// A warnings handler.
+///
+/// Receives a warning message without a trailing newline.
+pub type WarnHandler = fn(fmt::Arguments);
+
+/// The default warnings handler.
+///
+/// Logs warnings via `log::warn` when the `log` feature is enabled. Otherwise does nothing.
+pub fn default_warn_handler(args: fmt::Arguments) {
+    #[cfg(feature = "log")]
+    {
+        log::warn!("{}", args);
+    }
+
+    #[cfg(not(feature = "log"))]
+    {
+        let _ = args;
+    }
+}
+
+/// Style sheet parsing options.
+#[derive(Clone, Copy, Debug)]
+pub struct ParseOptions {
+    /// Skip declarations whose property name is not a valid CSS identifier.
+    ///
+    /// By default, any sequence of name characters is accepted, like `123`.
+    pub validate_property_names: bool,
+
+    /// A warnings handler.
+    ///
+    /// Default: `default_warn_handler`
+    pub warn_handler: WarnHandler,
+
+    /// A maximum nesting depth of `@supports` rules and pseudo-classes with selector arguments,
+    /// like `:not()` and `:is()`.
+    ///
+    /// Prevents a stack overflow on malicious input. Rules and selectors that exceed the limit
+    /// are skipped and reported as `Error::NestingLimitExceeded`.
+    ///
+    /// Default: 64
+    pub max_nesting_depth: usize,
+
+    /// A maximum style sheet size in bytes.
+    ///
+    /// Only the statements that end before the limit are parsed. The statement that crosses it
+    /// and the rest of the text are skipped and reported as `Error::InputSizeLimitExceeded`.
+    ///
+    /// Default: 64 MiB
+    pub max_input_size: usize,
+
+    /// Stop parsing after the first statement with an error.
+    ///
+    /// Also reports a missing closing `}` of the last rule as `Error::UnexpectedEndOfStream`,
+    /// which is tolerated otherwise.
+    ///
+    /// Default: false
+    pub strict: bool,
+}
+
+impl ParseOptions {
+    // Returns options for a nested rule or selector.
+    pub(crate) fn nested(self) -> Result<Self, Error> {
+        match self.max_nesting_depth.checked_sub(1) {
+            Some(max_nesting_depth) => Ok(ParseOptions { max_nesting_depth, ..self }),
+            None => {
+                warn!(self.warn_handler, "The nesting limit of {} was exceeded.", self.max_nesting_depth);
+                Err(Error::NestingLimitExceeded)
+            }
+        }
+    }
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            validate_property_names: false,
+            warn_handler: default_warn_handler,
+            max_nesting_depth: 64,
+            max_input_size: 64 * 1024 * 1024,
+            strict: false,
+        }
+    }
+}
+
+/// An `@import` rule.
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Import<'a> {
+    /// A style sheet URL.
+    ///
+    /// Stored as is, without any resolving or unescaping.
+    pub href: &'a str,
+    /// A media query list, like `screen, print`.
+    pub media: Option<&'a str>,
+}
+
+/// An `@namespace` rule.
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Namespace<'a> {
+    /// A namespace prefix, like `svg`.
+    ///
+    /// `None` for the default namespace.
+    pub prefix: Option<&'a str>,
+    /// A namespace URI, like `http://www.w3.org/2000/svg`.
+    ///
+    /// Stored as is, without any resolving or unescaping.
+    pub uri: &'a str,
+}
+
+/// An `@property` rule.
+///
+/// Cf. https://www.w3.org/TR/css-properties-values-api-1/#at-property-rule.
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Property<'a> {
+    /// A custom property name, like `--main-color`.
+    pub name: &'a str,
+    /// A `syntax` descriptor value without quotes, like `<color>`.
+    pub syntax: &'a str,
+    /// An `inherits` descriptor value.
+    pub inherits: bool,
+    /// An `initial-value` descriptor value.
+    ///
+    /// Can be omitted only when `syntax` is `*`.
+    pub initial_value: Option<&'a str>,
+}
+
+/// An `@font-face` rule.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FontFace<'a> {
+    /// A list of descriptors, like `font-family` and `src`.
+    ///
+    /// Just like with rules, values are not parsed.
+    #[cfg_attr(feature = "serde", serde(borrow))]
     pub declarations: Vec<Declaration<'a>>,
 }
 
+/// An `@keyframes` rule.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Keyframes<'a> {
+    /// An animation name.
+    pub name: &'a str,
+    /// A list of keyframes.
+    ///
+    /// Keyframes with multiple selectors, like `from, to { ... }`,
+    /// are split into separate ones with the same declarations.
+    #[cfg_attr(feature = "serde", serde(borrow))]
+    pub frames: Vec<(KeyframeSelector, Vec<Declaration<'a>>)>,
+}
+
+/// A keyframe selector.
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum KeyframeSelector {
+    /// `from`
+    From,
+    /// `to`
+    To,
+    /// A percentage in a 0..100 range, like `50%`.
+    Percent(f32),
+}
+
 /// A style sheet.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StyleSheet<'a> {
+    /// A charset declared via the `@charset` rule, like `UTF-8`.
+    ///
+    /// Only informational, since the text is already UTF-8.
+    /// When parsing multiple texts via `parse_more`, the first declared charset is kept.
+    #[cfg_attr(feature = "serde", serde(borrow, default))]
+    pub charset: Option<&'a str>,
+    /// A list of `@import` rules.
+    ///
+    /// Imported style sheets are not loaded.
+    /// The caller should resolve them and load via `parse_more`.
+    #[cfg_attr(feature = "serde", serde(borrow))]
+    pub imports: Vec<Import<'a>>,
+    /// A list of `@namespace` rules.
+    ///
+    /// Namespaces are not applied during matching.
+    /// See `Element::has_namespace_prefix` for details.
+    #[cfg_attr(feature = "serde", serde(borrow, default))]
+    pub namespaces: Vec<Namespace<'a>>,
+    /// A list of registered custom properties via `@property` rules.
+    #[cfg_attr(feature = "serde", serde(borrow, default))]
+    pub properties: Vec<Property<'a>>,
+    /// A list of `@font-face` rules.
+    #[cfg_attr(feature = "serde", serde(borrow, default))]
+    pub font_faces: Vec<FontFace<'a>>,
+    /// A list of `@keyframes` rules.
+    #[cfg_attr(feature = "serde", serde(borrow, default))]
+    pub keyframes: Vec<Keyframes<'a>>,
     /// A list of rules.
+    #[cfg_attr(feature = "serde", serde(borrow))]
     pub rules: Vec<Rule<'a>>,
 }
 
 impl<'a> StyleSheet<'a> {
     /// Creates an empty style sheet.
     pub fn new() -> Self {
-        StyleSheet { rules: Vec::new() }
+        StyleSheet {
+            charset: None,
+            imports: Vec::new(),
+            namespaces: Vec::new(),
+            properties: Vec::new(),
+            font_faces: Vec::new(),
+            keyframes: Vec::new(),
+            rules: Vec::new(),
+        }
     }
 
     /// Parses a style sheet from text.
     ///
-    /// At-rules are not supported and will be skipped.
+    /// At-rules are not supported and will be skipped. Except `@charset`, which will be
+    /// stored in `charset`, `@import`, which will be stored in `imports`, `@property`, which will be stored in `properties`,
+    /// `@font-face`, which will be stored in `font_faces`, and `@supports`,
+    /// which nested rules will be stored in `rules` with a condition set in `Rule::supports`.
+    /// Nested at-rules are skipped too, except `@supports`.
     ///
     /// # Errors
     ///
     /// Doesn't produce any errors. In worst case scenario will return an empty stylesheet.
     ///
-    /// All warnings will be logged.
+    /// A missing closing `}` of the last rule is tolerated, so truncated input like
+    /// `p { color: red` will still produce a `p` rule with a `color` declaration.
+    ///
+    /// All warnings will be logged. Use `parse_with_diagnostics` to collect them instead.
     pub fn parse(text: &'a str) -> Self {
+        Self::parse_with_options(text, ParseOptions::default())
+    }
+
+    /// Parses a style sheet from text using the specified options.
+    ///
+    /// See `parse` for details.
+    pub fn parse_with_options(text: &'a str, options: ParseOptions) -> Self {
         let mut sheet = StyleSheet::new();
-        sheet.parse_more(text);
+        sheet.parse_more_with_options(text, options);
         sheet
     }
 
-    /// Parses a style sheet from a text to the current style sheet.
-    pub fn parse_more(&mut self, text: &'a str) {
-        let mut s = Stream::from(text);
+    /// Parses a style sheet from text and collects all the recoverable errors.
+    ///
+    /// Unlike `parse`, returns a list of errors along with their positions
+    /// in the order they were encountered. The style sheet is the same as the one
+    /// produced by `parse`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use simplecss::{StyleSheet, Error, TextPos};
+    ///
+    /// let (sheet, diagnostics) = StyleSheet::parse_with_diagnostics("a > { color:red } b { color:green }");
+    /// assert_eq!(sheet.rules.len(), 1);
+    /// assert_eq!(diagnostics, vec![(TextPos::new(1, 1, 0), Error::SelectorMissing)]);
+    /// ```
+    pub fn parse_with_diagnostics(text: &'a str) -> (Self, Vec<(TextPos, Error)>) {
+        let mut sheet = StyleSheet::new();
+        let mut diagnostics = Vec::new();
+        sheet.parse_more_impl(Stream::from(text), ParseOptions::default(), &mut diagnostics);
+        (sheet, diagnostics)
+    }
 
-        if s.skip_spaces_and_comments().is_err() {
-            return;
+    /// Parses a style sheet from text and fails on the first error.
+    ///
+    /// Uses `ParseOptions::strict`, so a missing closing `}` of the last rule is an error as well.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error and its position, like `parse_with_diagnostics` does.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use simplecss::{StyleSheet, Error, TextPos};
+    ///
+    /// assert!(StyleSheet::try_parse("a { color:red } b { color:green }").is_ok());
+    /// assert_eq!(StyleSheet::try_parse("a { color:red } div").unwrap_err(),
+    ///            (TextPos::new(1, 17, 16), Error::UnexpectedEndOfStream));
+    /// assert_eq!(StyleSheet::try_parse("a { color:red").unwrap_err(),
+    ///            (TextPos::new(1, 1, 0), Error::UnexpectedEndOfStream));
+    /// ```
+    pub fn try_parse(text: &'a str) -> Result<Self, (TextPos, Error)> {
+        let mut sheet = StyleSheet::new();
+        let mut diagnostics = Vec::new();
+        let options = ParseOptions { strict: true, ..ParseOptions::default() };
+        sheet.parse_more_impl(Stream::from(text), options, &mut diagnostics);
+        match diagnostics.first() {
+            Some(e) => Err(*e),
+            None => Ok(sheet),
         }
+    }
 
-        while !s.at_end() {
-            if s.skip_spaces_and_comments().is_err() {
-                break;
-            }
+    /// Parses a style sheet from text and returns a byte offset where parsing was stopped.
+    ///
+    /// Parsing can be stopped only by an unrecoverable error, like an unclosed comment
+    /// or an exceeded `ParseOptions::max_input_size`.
+    /// In this case, the offset points to the start of the unparsed tail.
+    /// In the strict mode, it points to the start of the statement with an error.
+    /// Otherwise, it's equal to the text length.
+    ///
+    /// See `parse` for details.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use simplecss::StyleSheet;
+    ///
+    /// let (sheet, offset) = StyleSheet::parse_partial("a { color:red } /* b { color:green }");
+    /// assert_eq!(sheet.rules.len(), 1);
+    /// assert_eq!(offset, 16);
+    /// ```
+    pub fn parse_partial(text: &'a str) -> (Self, usize) {
+        let mut sheet = StyleSheet::new();
+        let (_, offset) = sheet.parse_more_impl(Stream::from(text), ParseOptions::default(), &mut Vec::new());
+        (sheet, offset)
+    }
 
-            let _ = consume_statement(&mut s, &mut self.rules);
-        }
+    /// Parses a style sheet from the `start..end` byte range of the text.
+    ///
+    /// Useful for style sheets embedded into other documents,
+    /// like a `<style>` element in HTML. Anything outside the range is ignored.
+    ///
+    /// See `parse` for details.
+    ///
+    /// # Panics
+    ///
+    /// - When `start` is bigger than `end`.
+    /// - When `end` is bigger than the text length.
+    /// - When `start` or `end` are not on a char boundary.
+    pub fn parse_bound(text: &'a str, start: usize, end: usize) -> Self {
+        Self::parse_bound_with_diagnostics(text, start, end).0
+    }
 
-        if !s.at_end() {
-            warn!("{} bytes were left.", s.slice_tail().len());
-        }
+    /// Parses a style sheet from the `start..end` byte range of the text
+    /// and collects all the recoverable errors.
+    ///
+    /// Errors positions are relative to the whole text and not to the range.
+    ///
+    /// See `parse_bound` and `parse_with_diagnostics` for details.
+    pub fn parse_bound_with_diagnostics(
+        text: &'a str,
+        start: usize,
+        end: usize,
+    ) -> (Self, Vec<(TextPos, Error)>) {
+        let mut sheet = StyleSheet::new();
+        let mut diagnostics = Vec::new();
+        let s = Stream::new_bound(text, start, end);
+        sheet.parse_more_impl(s, ParseOptions::default(), &mut diagnostics);
+        (sheet, diagnostics)
+    }
 
-        // Remove empty rules.
-        self.rules.retain(|rule| !rule.declarations.is_empty());
+    /// Parses a style sheet from a text to the current style sheet.
+    ///
+    /// Returns the number of added rules. Empty rules are not counted, since they are removed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use simplecss::StyleSheet;
+    ///
+    /// let mut sheet = StyleSheet::parse("a { color:red }");
+    /// assert_eq!(sheet.parse_more("b, c { color:green } d {}"), 2);
+    /// assert_eq!(sheet.parse_more("not a style sheet"), 0);
+    /// assert_eq!(sheet.rules.len(), 3);
+    /// ```
+    pub fn parse_more(&mut self, text: &'a str) -> usize {
+        self.parse_more_with_options(text, ParseOptions::default())
+    }
+
+    /// Parses a style sheet from a text to the current style sheet using the specified options.
+    ///
+    /// Returns the number of added rules.
+    pub fn parse_more_with_options(&mut self, text: &'a str, options: ParseOptions) -> usize {
+        self.parse_more_impl(Stream::from(text), options, &mut Vec::new()).0
+    }
+
+    fn parse_more_impl(
+        &mut self,
+        s: Stream<'a, '_>,
+        options: ParseOptions,
+        diagnostics: &mut Vec<(TextPos, Error)>,
+    ) -> (usize, usize) {
+        let rules_count = self.rules.len();
+        let next_order = self.rules.iter().map(|rule| rule.source_order + 1).max().unwrap_or(0);
+
+        // Errors positions are calculated quite often, so we have to cache line offsets.
+        let lines = LineIndex::new(s.text());
+        let mut s = s.with_line_index(&lines);
+
+        // A UTF-8 BOM is not a part of the style sheet.
+        if s.pos() == 0 && s.slice_tail().starts_with('\u{FEFF}') {
+            s.advance('\u{FEFF}'.len_utf8());
+        }
+
+        // Used to check that `@charset` is the first statement, which is not always at 0
+        // when parsing a range.
+        let text_start = s.pos();
+
+        let truncated = s.limit(options.max_input_size);
+
+        let mut stopped_at = None;
+        while !s.at_end() {
+            s.skip_spaces();
+            let start = s.pos();
+            if let Err(e) = s.skip_spaces_and_comments() {
+                if truncated {
+                    // The comment was cut by the size limit.
+                    stopped_at = Some(start);
+                    break;
+                }
+
+                warn!(options.warn_handler, "Style sheet parsing failed cause {}.", e);
+                diagnostics.push((s.gen_text_pos_from(start), e));
+
+                // An unclosed comment consumes the rest of the text, so point to its start instead.
+                stopped_at = Some(match e {
+                    Error::InvalidComment(pos) => pos.offset,
+                    _ => s.pos(),
+                });
+                break;
+            }
+
+            if s.at_end() {
+                break;
+            }
+
+            let start = s.pos();
+            let errors_count = diagnostics.len();
+            let statements_count = self.statements_count();
+            if let Err(e) = consume_statement(&mut s, text_start, options, self, diagnostics) {
+                diagnostics.push((s.gen_text_pos_from(start), e));
+            }
+
+            if truncated && s.at_end() {
+                // The statement may be cut by the size limit, so it must be discarded.
+                self.truncate_statements(statements_count);
+                diagnostics.truncate(errors_count);
+                stopped_at = Some(start);
+                break;
+            }
+
+            if options.strict && diagnostics.len() != errors_count {
+                stopped_at = Some(start);
+                break;
+            }
+        }
+
+        if truncated {
+            let pos = *stopped_at.get_or_insert(s.pos());
+            warn!(options.warn_handler, "The input size limit of {} bytes was exceeded.", options.max_input_size);
+            diagnostics.push((s.gen_text_pos_from(pos), Error::InputSizeLimitExceeded));
+        } else if !s.at_end() {
+            warn!(options.warn_handler, "{} bytes were left.", s.slice_tail().len());
+        }
+
+        let mut added = 0;
+        for rule in self.rules[rules_count..].iter_mut().filter(|rule| !rule.declarations.is_empty()) {
+            rule.source_order = next_order + added;
+            added += 1;
+        }
+
+        // Remove empty rules.
+        self.rules.retain(|rule| !rule.declarations.is_empty());
 
         // Sort the rules by specificity.
         self.rules.sort_by_cached_key(|rule| rule.selector.specificity());
+
+        (added, stopped_at.unwrap_or_else(|| s.pos()))
+    }
+
+    // Returns the number of parsed statements of each kind.
+    fn statements_count(&self) -> (bool, [usize; 6]) {
+        (self.charset.is_some(), [
+            self.imports.len(),
+            self.namespaces.len(),
+            self.properties.len(),
+            self.font_faces.len(),
+            self.keyframes.len(),
+            self.rules.len(),
+        ])
+    }
+
+    // Removes statements parsed after `statements_count` was called.
+    fn truncate_statements(&mut self, (has_charset, lens): (bool, [usize; 6])) {
+        if !has_charset {
+            self.charset = None;
+        }
+
+        self.imports.truncate(lens[0]);
+        self.namespaces.truncate(lens[1]);
+        self.properties.truncate(lens[2]);
+        self.font_faces.truncate(lens[3]);
+        self.keyframes.truncate(lens[4]);
+        self.rules.truncate(lens[5]);
+    }
+}
+
+impl<'a> StyleSheet<'a> {
+    /// Returns the number of rules.
+    ///
+    /// At-rules, like `@import` or `@font-face`, are not counted.
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    /// Checks that the style sheet has no rules.
+    ///
+    /// At-rules, like `@import` or `@font-face`, are not taken into account.
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Returns a namespace URI for the specified prefix.
+    ///
+    /// `None` prefix returns the default namespace. When a prefix is declared
+    /// multiple times, the last declaration wins.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use simplecss::StyleSheet;
+    ///
+    /// let sheet = StyleSheet::parse("@namespace svg url(http://www.w3.org/2000/svg);");
+    /// assert_eq!(sheet.namespace_uri(Some("svg")), Some("http://www.w3.org/2000/svg"));
+    /// assert_eq!(sheet.namespace_uri(None), None);
+    /// ```
+    pub fn namespace_uri(&self, prefix: Option<&str>) -> Option<&'a str> {
+        self.namespaces.iter().rev().find(|ns| ns.prefix == prefix).map(|ns| ns.uri)
+    }
+
+    /// Returns an iterator over rules that match the specified element.
+    ///
+    /// Rules are returned in the cascade order, i.e. sorted by specificity.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # struct Node;
+    /// # impl simplecss::Element for Node {
+    /// #     fn parent_element(&self) -> Option<Self> { None }
+    /// #     fn prev_sibling_element(&self) -> Option<Self> { None }
+    /// #     fn has_local_name(&self, name: &str) -> bool { name == "p" }
+    /// #     fn attribute_matches(&self, _: &str, _: simplecss::AttributeOperator) -> bool { false }
+    /// #     fn pseudo_class_matches(&self, _: simplecss::PseudoClass) -> bool { false }
+    /// # }
+    /// use simplecss::StyleSheet;
+    ///
+    /// let sheet = StyleSheet::parse("p { color:red } div { color:green } * { width:5px }");
+    /// let rules: Vec<_> = sheet.rules_for(&Node).map(|r| r.selector.to_string()).collect();
+    /// assert_eq!(rules, vec!["*", "p"]);
+    /// ```
+    pub fn rules_for<'b, E: Element>(&'b self, element: &'b E) -> impl Iterator<Item = &'b Rule<'a>> + 'b {
+        self.rules.iter().filter(move |rule| rule.matches(element))
+    }
+
+    /// Returns rules with a selector that is structurally equal to the specified one.
+    ///
+    /// Unlike `rules_for`, doesn't perform any matching.
+    /// See `Selector` for details about selectors comparison.
+    ///
+    /// Returns an empty list when `selector_text` is not a valid selector.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use simplecss::StyleSheet;
+    ///
+    /// let sheet = StyleSheet::parse(".btn { color:red } .btn, a > .btn { width:5px }");
+    /// assert_eq!(sheet.query(".btn").len(), 2);
+    /// assert_eq!(sheet.query("a>.btn").len(), 1);
+    /// assert!(sheet.query("a").is_empty());
+    /// ```
+    pub fn query(&self, selector_text: &str) -> Vec<&Rule<'a>> {
+        let selector = match Selector::parse(selector_text) {
+            Some(v) => v,
+            None => return Vec::new(),
+        };
+
+        self.rules.iter().filter(|rule| rule.selector == selector).collect()
+    }
+
+    /// Returns an iterator over rules that have a declaration with the specified property name.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use simplecss::StyleSheet;
+    ///
+    /// let sheet = StyleSheet::parse("a { display:none } b { color:red } c { display:block }");
+    /// let rules: Vec<_> = sheet.rules_with_property("display").map(|r| r.selector.to_string()).collect();
+    /// assert_eq!(rules, vec!["a", "c"]);
+    /// ```
+    pub fn rules_with_property<'b>(&'b self, name: &'b str) -> impl Iterator<Item = &'b Rule<'a>> + 'b {
+        self.rules.iter().filter(move |rule| rule.declarations.iter().any(|d| d.name == name))
+    }
+
+    /// Returns an iterator over all declarations with their rules selectors.
+    ///
+    /// Declarations are returned in the rules order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use simplecss::StyleSheet;
+    ///
+    /// let sheet = StyleSheet::parse("a { display:none; color:red } b { display:block }");
+    /// assert_eq!(sheet.iter_declarations().filter(|(_, d)| d.name == "display").count(), 2);
+    /// ```
+    pub fn iter_declarations(&self) -> impl Iterator<Item = (&Selector<'a>, &Declaration<'a>)> + '_ {
+        self.rules.iter().flat_map(|rule| rule.declarations.iter().map(move |d| (&rule.selector, d)))
+    }
+
+    /// Returns declarations that apply to the specified element.
+    ///
+    /// Declarations from rules with a higher specificity override the ones with a lower one.
+    /// Declarations with the same specificity are overridden by the later ones.
+    /// An `!important` declaration can only be overridden by another `!important` one.
+    ///
+    /// Rules origins are taken into account as well. Normal declarations of the author
+    /// override the user ones, which override the user agent ones.
+    /// For `!important` declarations the order is reversed.
+    ///
+    /// Declarations are returned in the order of the first appearance of their names.
+    pub fn declarations_for<E: Element>(&self, element: &E) -> Vec<&Declaration<'a>> {
+        let mut declarations: Vec<(&Declaration<'a>, u8)> = Vec::new();
+        for rule in self.rules.iter().filter(|rule| rule.selector.matches(element)) {
+            for dec in rule.declarations.iter() {
+                let level = rule.origin.cascade_level(dec.important);
+                match declarations.iter_mut().find(|(d, _)| d.name == dec.name) {
+                    Some(prev) => {
+                        if prev.1 <= level {
+                            *prev = (dec, level);
+                        }
+                    }
+                    None => declarations.push((dec, level)),
+                }
+            }
+        }
+
+        declarations.into_iter().map(|(dec, _)| dec).collect()
+    }
+
+    /// Converts the style sheet into an owned one.
+    ///
+    /// The style sheet is stored as text, like the one produced by `Display`,
+    /// so raw declaration values are trimmed and grouped selectors don't share
+    /// declarations anymore. Rules origins and source order are preserved.
+    pub fn into_owned(self) -> OwnedStyleSheet {
+        OwnedStyleSheet {
+            text: self.to_string(),
+            rules: self.rules.iter().map(|rule| (rule.origin, rule.source_order)).collect(),
+        }
+    }
+
+    /// Sets the origin of all the rules in the style sheet.
+    ///
+    /// Rules parsed later via `parse_more` will still be `Origin::Author`.
+    /// This way, style sheets from different origins can be combined into one.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use simplecss::{StyleSheet, Origin};
+    ///
+    /// let mut sheet = StyleSheet::parse("p { color:black }");
+    /// sheet.set_origin(Origin::UserAgent);
+    /// sheet.parse_more("p { color:red }");
+    /// assert_eq!(sheet.rules[0].origin, Origin::UserAgent);
+    /// assert_eq!(sheet.rules[1].origin, Origin::Author);
+    /// ```
+    pub fn set_origin(&mut self, origin: Origin) {
+        for rule in &mut self.rules {
+            rule.origin = origin;
+        }
+    }
+
+    /// Retains only the rules specified by the predicate.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use simplecss::StyleSheet;
+    ///
+    /// let mut sheet = StyleSheet::parse("@supports (display:grid) { p { color:red } } a { color:green }");
+    /// sheet.retain_rules(|rule| rule.supports.is_empty());
+    /// assert_eq!(sheet.to_string(), "a { color:green; }");
+    /// ```
+    pub fn retain_rules<F: FnMut(&Rule<'a>) -> bool>(&mut self, f: F) {
+        self.rules.retain(f);
+    }
+
+    /// Returns a copy of the style sheet with only the rules specified by the predicate.
+    ///
+    /// Unlike `retain_rules`, the original style sheet is preserved.
+    /// Declarations are shared with the original rules and at-rules are copied as is.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use simplecss::StyleSheet;
+    ///
+    /// let sheet = StyleSheet::parse("p { color:red } a { color:green }");
+    /// let links = sheet.filter_rules(|rule| rule.selector.to_string() == "a");
+    /// assert_eq!(links.to_string(), "a { color:green; }");
+    /// assert_eq!(sheet.len(), 2);
+    /// ```
+    pub fn filter_rules<F: FnMut(&Rule<'a>) -> bool>(&self, mut f: F) -> StyleSheet<'a> {
+        StyleSheet {
+            charset: self.charset,
+            imports: self.imports.clone(),
+            namespaces: self.namespaces.clone(),
+            properties: self.properties.clone(),
+            font_faces: self.font_faces.clone(),
+            keyframes: self.keyframes.clone(),
+            rules: self.rules.iter().filter(|rule| f(rule)).cloned().collect(),
+        }
+    }
+
+    /// Removes rules with pseudo-elements that are not in the `supported` list.
+    ///
+    /// Rules without pseudo-elements are preserved.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use simplecss::StyleSheet;
+    ///
+    /// let mut sheet = StyleSheet::parse("p { color:red } p::before { color:green } p::selection { color:blue }");
+    /// sheet.retain_supported_pseudo_elements(&["before", "after"]);
+    /// assert_eq!(sheet.to_string(), "p { color:red; }\np::before { color:green; }");
+    /// ```
+    pub fn retain_supported_pseudo_elements(&mut self, supported: &[&str]) {
+        self.rules.retain(|rule| match rule.selector.pseudo_element() {
+            Some(name) => supported.contains(&name),
+            None => true,
+        });
+    }
+
+    /// Removes shadowed declarations from each rule.
+    ///
+    /// Only the last declaration with the same name is preserved,
+    /// unless an earlier one is `!important` and the later one is not.
+    /// The order of the remaining declarations is preserved.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use simplecss::StyleSheet;
+    ///
+    /// let mut sheet = StyleSheet::parse("p { color:red !important; fill:red; color:blue; fill:green }");
+    /// sheet.deduplicate_declarations();
+    /// assert_eq!(sheet.to_string(), "p { color:red !important;fill:green; }");
+    /// ```
+    pub fn deduplicate_declarations(&mut self) {
+        // Grouped selectors share the same declarations, so we have to process them only once.
+        // Old declarations are stored too, so their addresses cannot be reused.
+        let mut processed = BTreeMap::new();
+        for rule in &mut self.rules {
+            let key = Arc::as_ptr(&rule.declarations) as usize;
+            if let Some((_, declarations)) = processed.get(&key) {
+                rule.declarations = Arc::clone(declarations);
+                continue;
+            }
+
+            // A declaration is shadowed by any later one with the same name
+            // or by an earlier `!important` one.
+            let is_shadowed = |idx: usize, d: &Declaration| {
+                rule.declarations.iter().enumerate().any(|(idx2, d2)| {
+                    d2.name == d.name && match idx2.cmp(&idx) {
+                        core::cmp::Ordering::Less => d2.important && !d.important,
+                        core::cmp::Ordering::Equal => false,
+                        core::cmp::Ordering::Greater => d2.important || !d.important,
+                    }
+                })
+            };
+
+            let mut declarations = Arc::clone(&rule.declarations);
+            if declarations.iter().enumerate().any(|(i, d)| is_shadowed(i, d)) {
+                declarations = Arc::new(
+                    rule.declarations.iter().enumerate()
+                        .filter(|(i, d)| !is_shadowed(*i, d))
+                        .map(|(_, d)| d.clone())
+                        .collect()
+                );
+            }
+
+            let old = core::mem::replace(&mut rule.declarations, Arc::clone(&declarations));
+            processed.insert(key, (old, declarations));
+        }
+    }
+
+    /// Returns a minified style sheet.
+    ///
+    /// Unlike `Display`, doesn't write any optional whitespaces and trailing semicolons.
+    /// Stored at-rules, like `@charset` and `@import`, are written first.
+    /// Adjacent rules with the same declarations will be grouped
+    /// and duplicated selectors in such groups removed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use simplecss::StyleSheet;
+    ///
+    /// let sheet = StyleSheet::parse("a > b { color: red; width: 5px } c + d { color: red; width: 5px }");
+    /// assert_eq!(sheet.to_minified_string(), "a>b,c+d{color:red;width:5px}");
+    /// ```
+    pub fn to_minified_string(&self) -> String {
+        use core::fmt::Write;
+
+        let mut out = String::new();
+        let _ = self.write_at_rules(&mut out, WriteStyle::Minified);
+
+        let mut i = 0;
+        while i < self.rules.len() {
+            let declarations = &self.rules[i].declarations;
+            let supports = &self.rules[i].supports;
+
+            let mut selectors: Vec<String> = Vec::new();
+            while i < self.rules.len()
+                && self.rules[i].declarations == *declarations
+                && self.rules[i].supports == *supports
+            {
+                let selector = format!("{:#}", self.rules[i].selector);
+                if !selectors.contains(&selector) {
+                    selectors.push(selector);
+                }
+
+                i += 1;
+            }
+
+            for condition in supports {
+                let _ = write!(out, "@supports {}{{", condition);
+            }
+
+            out.push_str(&selectors.join(","));
+            out.push('{');
+            for (j, dec) in declarations.iter().enumerate() {
+                if j != 0 {
+                    out.push(';');
+                }
+
+                let _ = write!(out, "{}:{}", dec.name, dec.value);
+                if dec.important {
+                    out.push_str("!important");
+                }
+            }
+            out.push('}');
+
+            for _ in supports {
+                out.push('}');
+            }
+        }
+
+        out
+    }
+
+    /// Writes a human-readable style sheet.
+    ///
+    /// Unlike `Display`, each declaration is written on its own line and indented
+    /// by `indent` spaces. Rules are separated by an empty line.
+    /// Stored at-rules, like `@charset` and `@import`, are written first.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use simplecss::StyleSheet;
+    ///
+    /// let sheet = StyleSheet::parse("a { color:red; width:5px !important }");
+    /// let mut out = String::new();
+    /// sheet.write_pretty(&mut out, 2).unwrap();
+    /// assert_eq!(out, "a {\n  color: red;\n  width: 5px !important;\n}\n");
+    /// ```
+    pub fn write_pretty<W: fmt::Write>(&self, w: &mut W, indent: usize) -> fmt::Result {
+        let has_at_rules = self.write_at_rules(w, WriteStyle::Pretty(indent))?;
+        for (i, rule) in self.rules.iter().enumerate() {
+            if i != 0 || has_at_rules {
+                writeln!(w)?;
+            }
+
+            let mut depth = 0;
+            for condition in &rule.supports {
+                writeln!(w, "{:2$}@supports {} {{", "", condition, depth * indent)?;
+                depth += 1;
+            }
+
+            writeln!(w, "{:2$}{} {{", "", rule.selector, depth * indent)?;
+            for dec in rule.declarations.iter() {
+                write!(w, "{:3$}{}: {}", "", dec.name, dec.value, (depth + 1) * indent)?;
+                if dec.important {
+                    write!(w, " !important")?;
+                }
+                writeln!(w, ";")?;
+            }
+            writeln!(w, "{:1$}}}", "", depth * indent)?;
+
+            while depth != 0 {
+                depth -= 1;
+                writeln!(w, "{:1$}}}", "", depth * indent)?;
+            }
+        }
+
+        Ok(())
     }
 }
 
 impl fmt::Display for StyleSheet<'_> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let has_at_rules = self.write_at_rules(f, WriteStyle::Normal)?;
+        if has_at_rules && !self.rules.is_empty() {
+            writeln!(f)?;
+        }
+
         for (i, rule) in self.rules.iter().enumerate() {
+            for condition in &rule.supports {
+                write!(f, "@supports {} {{ ", condition)?;
+            }
+
             write!(f, "{} {{ ", rule.selector)?;
-            for dec in &rule.declarations {
+            for dec in rule.declarations.iter() {
                 write!(f, "{}:{}", dec.name, dec.value)?;
                 if dec.important {
                     write!(f, " !important")?;
                 }
-                write!(f, ";")?;
+                write!(f, ";")?;
+            }
+            write!(f, " }}")?;
+
+            for _ in &rule.supports {
+                write!(f, " }}")?;
+            }
+
+            if i != self.rules.len() - 1 {
+                writeln!(f)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum WriteStyle {
+    /// Like `Display`.
+    Normal,
+    Minified,
+    /// With an indent.
+    Pretty(usize),
+}
+
+impl StyleSheet<'_> {
+    // Writes the stored at-rules in the order they must appear in a style sheet.
+    //
+    // Returns `true` when anything was written.
+    fn write_at_rules<W: fmt::Write>(&self, w: &mut W, style: WriteStyle) -> Result<bool, fmt::Error> {
+        // Pretty statements are written on their own lines and blocks are separated by an empty line.
+        let mut written = false;
+        let mut prev_is_block = false;
+        let mut separate = |w: &mut W, is_block: bool| -> fmt::Result {
+            match style {
+                WriteStyle::Normal if written => writeln!(w)?,
+                WriteStyle::Pretty(_) if written && (is_block || prev_is_block) => writeln!(w)?,
+                _ => {}
+            }
+
+            written = true;
+            prev_is_block = is_block;
+            Ok(())
+        };
+
+        let end = if let WriteStyle::Pretty(_) = style { "\n" } else { "" };
+
+        if let Some(charset) = self.charset {
+            separate(w, false)?;
+            write!(w, "@charset ")?;
+            write_quoted(w, charset)?;
+            write!(w, ";{}", end)?;
+        }
+
+        for import in &self.imports {
+            separate(w, false)?;
+            write!(w, "@import ")?;
+            write_quoted(w, import.href)?;
+            if let Some(media) = import.media {
+                write!(w, " {}", media)?;
+            }
+            write!(w, ";{}", end)?;
+        }
+
+        for namespace in &self.namespaces {
+            separate(w, false)?;
+            write!(w, "@namespace ")?;
+            if let Some(prefix) = namespace.prefix {
+                write!(w, "{} ", prefix)?;
+            }
+            write_quoted(w, namespace.uri)?;
+            write!(w, ";{}", end)?;
+        }
+
+        for property in &self.properties {
+            separate(w, true)?;
+            let syntax = format!("\"{}\"", property.syntax);
+            let mut descriptors = vec![
+                Declaration::new("syntax", &syntax, false),
+                Declaration::new("inherits", if property.inherits { "true" } else { "false" }, false),
+            ];
+            if let Some(value) = property.initial_value {
+                descriptors.push(Declaration::new("initial-value", value, false));
+            }
+
+            write!(w, "@property {}", property.name)?;
+            write_declarations_block(w, &descriptors, style, 0)?;
+            write!(w, "{}", end)?;
+        }
+
+        for font_face in &self.font_faces {
+            separate(w, true)?;
+            write!(w, "@font-face")?;
+            write_declarations_block(w, &font_face.declarations, style, 0)?;
+            write!(w, "{}", end)?;
+        }
+
+        for keyframes in &self.keyframes {
+            separate(w, true)?;
+            write!(w, "@keyframes {}", keyframes.name)?;
+            match style {
+                WriteStyle::Minified => write!(w, "{{")?,
+                WriteStyle::Normal => write!(w, " {{")?,
+                WriteStyle::Pretty(_) => writeln!(w, " {{")?,
+            }
+
+            for (selector, declarations) in &keyframes.frames {
+                match style {
+                    WriteStyle::Minified => {}
+                    WriteStyle::Normal => write!(w, " ")?,
+                    WriteStyle::Pretty(indent) => write!(w, "{:1$}", "", indent)?,
+                }
+
+                match selector {
+                    KeyframeSelector::From => write!(w, "from")?,
+                    KeyframeSelector::To => write!(w, "to")?,
+                    KeyframeSelector::Percent(n) => write!(w, "{}%", n)?,
+                }
+
+                write_declarations_block(w, declarations, style, 1)?;
+                write!(w, "{}", end)?;
+            }
+
+            match style {
+                WriteStyle::Normal => write!(w, " }}")?,
+                _ => write!(w, "}}{}", end)?,
+            }
+        }
+
+        Ok(written)
+    }
+}
+
+// Writes a string using double quotes, unless it contains them.
+fn write_quoted<W: fmt::Write>(w: &mut W, text: &str) -> fmt::Result {
+    let quote = if text.contains('"') { '\'' } else { '"' };
+    write!(w, "{0}{1}{0}", quote, text)
+}
+
+fn write_declarations_block<W: fmt::Write>(
+    w: &mut W,
+    declarations: &[Declaration],
+    style: WriteStyle,
+    depth: usize,
+) -> fmt::Result {
+    match style {
+        WriteStyle::Normal => {
+            write!(w, " {{ ")?;
+            for dec in declarations {
+                write!(w, "{}:{}", dec.name, dec.value)?;
+                if dec.important {
+                    write!(w, " !important")?;
+                }
+                write!(w, ";")?;
+            }
+            write!(w, " }}")
+        }
+        WriteStyle::Minified => {
+            write!(w, "{{")?;
+            for (i, dec) in declarations.iter().enumerate() {
+                if i != 0 {
+                    write!(w, ";")?;
+                }
+
+                write!(w, "{}:{}", dec.name, dec.value)?;
+                if dec.important {
+                    write!(w, "!important")?;
+                }
+            }
+            write!(w, "}}")
+        }
+        WriteStyle::Pretty(indent) => {
+            writeln!(w, " {{")?;
+            for dec in declarations {
+                write!(w, "{:3$}{}: {}", "", dec.name, dec.value, (depth + 1) * indent)?;
+                if dec.important {
+                    write!(w, " !important")?;
+                }
+                writeln!(w, ";")?;
+            }
+            write!(w, "{:1$}}}", "", depth * indent)
+        }
+    }
+}
+
+impl<'a> Default for StyleSheet<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Iterates over rules.
+impl<'a> IntoIterator for StyleSheet<'a> {
+    type Item = Rule<'a>;
+    type IntoIter = alloc::vec::IntoIter<Rule<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.rules.into_iter()
+    }
+}
+
+/// Iterates over rules.
+impl<'a, 'b> IntoIterator for &'b StyleSheet<'a> {
+    type Item = &'b Rule<'a>;
+    type IntoIter = core::slice::Iter<'b, Rule<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.rules.iter()
+    }
+}
+
+fn consume_statement<'a>(
+    s: &mut Stream<'a, '_>,
+    text_start: usize,
+    options: ParseOptions,
+    sheet: &mut StyleSheet<'a>,
+    diagnostics: &mut Vec<(TextPos, Error)>,
+) -> Result<(), Error> {
+    if s.curr_byte() == Ok(b'@') {
+        consume_at_rule(s, text_start, options, sheet, diagnostics)
+    } else {
+        consume_rule_set(s, options, &mut sheet.rules, diagnostics)
+    }
+}
+
+fn consume_at_rule<'a>(
+    s: &mut Stream<'a, '_>,
+    text_start: usize,
+    options: ParseOptions,
+    sheet: &mut StyleSheet<'a>,
+    diagnostics: &mut Vec<(TextPos, Error)>,
+) -> Result<(), Error> {
+    let start = s.pos();
+    s.consume_byte(b'@')?;
+    let ident = s.consume_ident()?;
+
+    if ident == "charset" {
+        // `@charset` must be the very first thing in a style sheet, except a BOM.
+        if start != text_start {
+            warn!(options.warn_handler, "The @charset rule must be the first one. Skipped.");
+            diagnostics.push((s.gen_text_pos_from(start), Error::UnsupportedAtRule));
+            skip_at_rule(s);
+            return Ok(());
+        }
+
+        match consume_charset(s) {
+            Ok(charset) => {
+                if sheet.charset.is_none() {
+                    sheet.charset = Some(charset);
+                }
+            }
+            Err(e) => {
+                warn!(options.warn_handler, "Invalid @charset rule cause {}. Skipped.", e);
+                diagnostics.push((s.gen_text_pos_from(start), e));
+                s.skip_bytes(|c| c != b';');
+                s.try_consume_byte(b';');
+            }
+        }
+
+        return Ok(());
+    }
+
+    if ident == "import" {
+        match consume_import(s) {
+            Ok(import) => sheet.imports.push(import),
+            Err(e) => {
+                warn!(options.warn_handler, "Invalid @import rule cause {}. Skipped.", e);
+                diagnostics.push((s.gen_text_pos_from(start), e));
+                s.skip_bytes(|c| c != b';');
+                s.try_consume_byte(b';');
+            }
+        }
+
+        return Ok(());
+    }
+
+    if ident == "namespace" {
+        match consume_namespace(s) {
+            Ok(namespace) => sheet.namespaces.push(namespace),
+            Err(e) => {
+                warn!(options.warn_handler, "Invalid @namespace rule cause {}. Skipped.", e);
+                diagnostics.push((s.gen_text_pos_from(start), e));
+                s.skip_bytes(|c| c != b';');
+                s.try_consume_byte(b';');
+            }
+        }
+
+        return Ok(());
+    }
+
+    if ident == "supports" {
+        return consume_supports(s, text_start, options, sheet, diagnostics);
+    }
+
+    if ident == "font-face" {
+        match consume_font_face(s, options, diagnostics) {
+            Ok(font_face) => sheet.font_faces.push(font_face),
+            Err(e) => {
+                warn!(options.warn_handler, "Invalid @font-face rule cause {}. Skipped.", e);
+                diagnostics.push((s.gen_text_pos_from(start), e));
+                skip_at_rule(s);
+            }
+        }
+
+        return Ok(());
+    }
+
+    if ident == "keyframes" {
+        match consume_keyframes(s, options, diagnostics) {
+            Ok(keyframes) => sheet.keyframes.push(keyframes),
+            Err(e) => {
+                warn!(options.warn_handler, "Invalid @keyframes rule cause {}. Skipped.", e);
+                diagnostics.push((s.gen_text_pos_from(start), e));
+                skip_at_rule(s);
+            }
+        }
+
+        return Ok(());
+    }
+
+    if ident == "property" {
+        match consume_property(s, options, diagnostics) {
+            Ok((name, declarations)) => match property_from_descriptors(name, &declarations) {
+                Some(property) => sheet.properties.push(property),
+                None => {
+                    warn!(options.warn_handler, "Invalid @property {} rule. Skipped.", name);
+                    let pos = s.gen_text_pos_from(start);
+                    diagnostics.push((pos, Error::InvalidValue(pos)));
+                }
+            },
+            Err(e) => {
+                warn!(options.warn_handler, "Invalid @property rule cause {}. Skipped.", e);
+                diagnostics.push((s.gen_text_pos_from(start), e));
+                skip_at_rule(s);
+            }
+        }
+
+        return Ok(());
+    }
+
+    warn!(options.warn_handler, "The @{} rule is not supported. Skipped.", ident);
+    diagnostics.push((s.gen_text_pos_from(start), Error::UnsupportedAtRule));
+
+    s.skip_bytes(|c| c != b';' && c != b'{');
+
+    match s.curr_byte()? {
+        b';' => s.advance(1),
+        b'{' => consume_block(s),
+        _ => {}
+    }
+
+    Ok(())
+}
+
+fn consume_supports<'a>(
+    s: &mut Stream<'a, '_>,
+    text_start: usize,
+    options: ParseOptions,
+    sheet: &mut StyleSheet<'a>,
+    diagnostics: &mut Vec<(TextPos, Error)>,
+) -> Result<(), Error> {
+    let condition = s.consume_bytes(|c| c != b'{' && c != b';').trim();
+    if condition.is_empty() || s.curr_byte()? != b'{' {
+        let pos = s.gen_text_pos();
+        skip_at_rule(s);
+        return Err(Error::InvalidValue(pos));
+    }
+
+    let options = match options.nested() {
+        Ok(v) => v,
+        Err(e) => {
+            consume_block(s);
+            return Err(e);
+        }
+    };
+
+    s.advance(1);
+
+    let start_rule_idx = sheet.rules.len();
+    let res = consume_supports_body(s, text_start, options, sheet, diagnostics);
+
+    // Nested groups were already processed, so the current condition is the outermost one.
+    for rule in sheet.rules.iter_mut().skip(start_rule_idx) {
+        rule.supports.insert(0, condition);
+    }
+
+    res
+}
+
+fn consume_supports_body<'a>(
+    s: &mut Stream<'a, '_>,
+    text_start: usize,
+    options: ParseOptions,
+    sheet: &mut StyleSheet<'a>,
+    diagnostics: &mut Vec<(TextPos, Error)>,
+) -> Result<(), Error> {
+    loop {
+        s.skip_spaces_and_comments()?;
+        if s.at_end() {
+            return Ok(());
+        }
+
+        if s.curr_byte_unchecked() == b'}' {
+            s.advance(1);
+            return Ok(());
+        }
+
+        let start = s.pos();
+        if let Err(e) = consume_statement(s, text_start, options, sheet, diagnostics) {
+            diagnostics.push((s.gen_text_pos_from(start), e));
+        }
+    }
+}
+
+fn skip_at_rule(s: &mut Stream) {
+    s.skip_bytes(|c| c != b';' && c != b'{');
+    match s.curr_byte() {
+        Ok(b';') => s.advance(1),
+        Ok(b'{') => consume_block(s),
+        _ => {}
+    }
+}
+
+fn consume_charset<'a>(s: &mut Stream<'a, '_>) -> Result<&'a str, Error> {
+    s.skip_spaces();
+    let charset = match s.curr_byte()? {
+        b'\'' | b'"' => s.consume_string()?,
+        _ => return Err(Error::InvalidValue(s.gen_text_pos())),
+    };
+    s.skip_spaces();
+    s.consume_byte(b';')?;
+
+    Ok(charset)
+}
+
+fn consume_import<'a>(s: &mut Stream<'a, '_>) -> Result<Import<'a>, Error> {
+    s.skip_spaces_and_comments()?;
+    let href = consume_href(s)?;
+    s.skip_spaces_and_comments()?;
+
+    let media = s.consume_bytes(|c| c != b';').trim();
+    let media = if media.is_empty() { None } else { Some(media) };
+
+    s.try_consume_byte(b';');
+
+    Ok(Import { href, media })
+}
+
+fn consume_namespace<'a>(s: &mut Stream<'a, '_>) -> Result<Namespace<'a>, Error> {
+    s.skip_spaces_and_comments()?;
+
+    let mut prefix = None;
+    if !s.slice_tail().starts_with("url(") && !matches!(s.curr_byte()?, b'\'' | b'"') {
+        prefix = Some(s.consume_ident()?);
+        s.skip_spaces_and_comments()?;
+    }
+
+    let uri = consume_href(s)?;
+
+    s.skip_spaces_and_comments()?;
+    if !s.at_end() {
+        s.consume_byte(b';')?;
+    }
+
+    Ok(Namespace { prefix, uri })
+}
+
+// Parses a URL in `@import` and `@namespace` rules, like `'a.css'` or `url(a.css)`.
+fn consume_href<'a>(s: &mut Stream<'a, '_>) -> Result<&'a str, Error> {
+    let href = if s.slice_tail().starts_with("url(") {
+        s.advance(4);
+        s.skip_spaces();
+        let href = match s.curr_byte()? {
+            b'\'' | b'"' => s.consume_string()?,
+            _ => s.consume_bytes(|c| c != b')').trim_end(),
+        };
+        s.skip_spaces();
+        s.consume_byte(b')')?;
+        href
+    } else {
+        match s.curr_byte()? {
+            b'\'' | b'"' => s.consume_string()?,
+            _ => return Err(Error::InvalidValue(s.gen_text_pos())),
+        }
+    };
+
+    Ok(href)
+}
+
+fn consume_font_face<'a>(
+    s: &mut Stream<'a, '_>,
+    options: ParseOptions,
+    diagnostics: &mut Vec<(TextPos, Error)>,
+) -> Result<FontFace<'a>, Error> {
+    s.skip_spaces_and_comments()?;
+    s.consume_byte(b'{')?;
+    let declarations = consume_declarations(s, options, diagnostics)?;
+    s.try_consume_byte(b'}');
+
+    Ok(FontFace { declarations })
+}
+
+fn consume_keyframes<'a>(
+    s: &mut Stream<'a, '_>,
+    options: ParseOptions,
+    diagnostics: &mut Vec<(TextPos, Error)>,
+) -> Result<Keyframes<'a>, Error> {
+    s.skip_spaces_and_comments()?;
+    let name = match s.curr_byte()? {
+        b'\'' | b'"' => s.consume_string()?,
+        _ => s.consume_ident()?,
+    };
+
+    s.skip_spaces_and_comments()?;
+    s.consume_byte(b'{')?;
+
+    let mut frames = Vec::new();
+    loop {
+        s.skip_spaces_and_comments()?;
+        if s.at_end() || s.curr_byte() == Ok(b'}') {
+            break;
+        }
+
+        let start = s.pos();
+        let selectors = s.consume_bytes(|c| c != b'{' && c != b'}');
+        let selectors: Option<Vec<_>> = selectors.split(',').map(parse_keyframe_selector).collect();
+
+        let declarations = if s.curr_byte() == Ok(b'{') {
+            s.advance(1);
+            let declarations = consume_declarations(s, options, diagnostics)?;
+            s.try_consume_byte(b'}');
+            Some(declarations)
+        } else {
+            None
+        };
+
+        match (selectors, declarations) {
+            (Some(selectors), Some(declarations)) => {
+                for selector in selectors {
+                    frames.push((selector, declarations.clone()));
+                }
             }
-            write!(f, " }}")?;
-
-            if i != self.rules.len() - 1 {
-                writeln!(f)?;
+            _ => {
+                warn!(options.warn_handler, "Invalid keyframe. Skipped.");
+                let pos = s.gen_text_pos_from(start);
+                diagnostics.push((pos, Error::InvalidValue(pos)));
             }
         }
-
-        Ok(())
     }
-}
 
-impl<'a> Default for StyleSheet<'a> {
-    fn default() -> Self {
-        Self::new()
-    }
+    s.try_consume_byte(b'}');
+
+    Ok(Keyframes { name, frames })
 }
 
-fn consume_statement<'a>(s: &mut Stream<'a>, rules: &mut Vec<Rule<'a>>) -> Result<(), Error> {
-    if s.curr_byte() == Ok(b'@') {
-        s.advance(1);
-        consume_at_rule(s)
+fn parse_keyframe_selector(text: &str) -> Option<KeyframeSelector> {
+    let text = text.trim();
+    if text.eq_ignore_ascii_case("from") {
+        Some(KeyframeSelector::From)
+    } else if text.eq_ignore_ascii_case("to") {
+        Some(KeyframeSelector::To)
     } else {
-        consume_rule_set(s, rules)
+        let n = color::parse_number(text.strip_suffix('%')?)?;
+        if (0.0..=100.0).contains(&n) {
+            Some(KeyframeSelector::Percent(n as f32))
+        } else {
+            None
+        }
     }
 }
 
-fn consume_at_rule(s: &mut Stream) -> Result<(), Error> {
-    let ident = s.consume_ident()?;
-    warn!("The @{} rule is not supported. Skipped.", ident);
+fn consume_property<'a>(
+    s: &mut Stream<'a, '_>,
+    options: ParseOptions,
+    diagnostics: &mut Vec<(TextPos, Error)>,
+) -> Result<(&'a str, Vec<Declaration<'a>>), Error> {
+    s.skip_spaces_and_comments()?;
+    let start = s.pos();
+    let name = s.consume_ident()?;
+    if !name.starts_with("--") {
+        return Err(Error::InvalidIdent(s.gen_text_pos_from(start)));
+    }
 
-    s.skip_bytes(|c| c != b';' && c != b'{');
+    s.skip_spaces_and_comments()?;
+    s.consume_byte(b'{')?;
+    let declarations = consume_declarations(s, options, diagnostics)?;
+    s.try_consume_byte(b'}');
 
-    match s.curr_byte()? {
-        b';' => s.advance(1),
-        b'{' => consume_block(s),
-        _ => {}
+    Ok((name, declarations))
+}
+
+fn property_from_descriptors<'a>(name: &'a str, declarations: &[Declaration<'a>]) -> Option<Property<'a>> {
+    // The last descriptor wins.
+    let descriptor = |name: &str| declarations.iter().rev().find(|d| d.name == name).map(|d| d.value);
+
+    let syntax = descriptor("syntax")?;
+    let syntax = match syntax.as_bytes().first() {
+        Some(&quote) if (quote == b'"' || quote == b'\'') && syntax.len() > 1
+                        && syntax.as_bytes()[syntax.len() - 1] == quote => &syntax[1..syntax.len() - 1],
+        _ => return None,
+    };
+
+    let inherits = match descriptor("inherits")? {
+        v if v.eq_ignore_ascii_case("true") => true,
+        v if v.eq_ignore_ascii_case("false") => false,
+        _ => return None,
+    };
+
+    let initial_value = descriptor("initial-value");
+    if initial_value.is_none() && syntax.trim() != "*" {
+        return None;
     }
 
-    Ok(())
+    Some(Property { name, syntax, inherits, initial_value })
 }
 
-fn consume_rule_set<'a>(s: &mut Stream<'a>, rules: &mut Vec<Rule<'a>>) -> Result<(), Error> {
+fn consume_rule_set<'a>(
+    s: &mut Stream<'a, '_>,
+    options: ParseOptions,
+    rules: &mut Vec<Rule<'a>>,
+    diagnostics: &mut Vec<(TextPos, Error)>,
+) -> Result<(), Error> {
     let start_rule_idx = rules.len();
 
     while s.curr_byte()? == b',' || start_rule_idx == rules.len() {
@@ -280,12 +2193,28 @@ fn consume_rule_set<'a>(s: &mut Stream<'a>, rules: &mut Vec<Rule<'a>>) -> Result
             s.advance(1);
         }
 
-        let (selector, offset) = crate::selector::parse(s.slice_tail());
+        s.skip_spaces();
+        let start = s.pos();
+        let (selector, offset) = crate::selector::parse(s.slice_tail(), options);
         s.advance(offset);
         s.skip_spaces();
 
-        if let Some(selector) = selector {
-            rules.push(Rule { selector, declarations: Vec::new() });
+        match selector {
+            Ok(selector) => rules.push(Rule {
+                selector,
+                declarations: Arc::default(),
+                origin: Origin::Author,
+                supports: Vec::new(),
+                source_order: 0,
+            }),
+            Err(e) => {
+                diagnostics.push((s.gen_text_pos_from(start), e));
+
+                // The end of the text was caused by the same error, so don't report it twice.
+                if s.at_end() {
+                    return Ok(());
+                }
+            }
         }
 
         match s.curr_byte()? {
@@ -300,11 +2229,16 @@ fn consume_rule_set<'a>(s: &mut Stream<'a>, rules: &mut Vec<Rule<'a>>) -> Result
 
     s.try_consume_byte(b'{');
 
-    let declarations = consume_declarations(s)?;
+    // Grouped selectors share the same declarations.
+    let declarations = Arc::new(consume_declarations(s, options, diagnostics)?);
     for rule in rules.iter_mut().skip(start_rule_idx) {
         rule.declarations = declarations.clone();
     }
 
+    if options.strict && s.at_end() {
+        return Err(Error::UnexpectedEndOfStream);
+    }
+
     s.try_consume_byte(b'}');
 
     Ok(())
@@ -342,15 +2276,49 @@ fn consume_until_block_end(s: &mut Stream) {
     s.try_consume_byte(b'}');
 }
 
-fn consume_declarations<'a>(s: &mut Stream<'a>) -> Result<Vec<Declaration<'a>>, Error> {
+fn consume_declarations<'a>(
+    s: &mut Stream<'a, '_>,
+    options: ParseOptions,
+    diagnostics: &mut Vec<(TextPos, Error)>,
+) -> Result<Vec<Declaration<'a>>, Error> {
     let mut declarations = Vec::new();
 
-    while !s.at_end() && s.curr_byte() != Ok(b'}') {
+    loop {
+        let start = s.pos();
+        if let Err(e) = s.skip_spaces_and_comments() {
+            // An unclosed comment consumes the rest of the text,
+            // but the declarations before it are still valid.
+            warn!(options.warn_handler, "Declarations parsing failed cause {}.", e);
+            diagnostics.push((s.gen_text_pos_from(start), e));
+            break;
+        }
+
+        if s.at_end() || s.curr_byte() == Ok(b'}') {
+            break;
+        }
+
+        let start = s.pos();
+        let checkpoint = *s;
         match consume_declaration(s) {
-            Ok(declaration) => declarations.push(declaration),
-            Err(_) => {
-                consume_until_block_end(s);
-                break;
+            Ok(declaration) => {
+                // `name` has escapes decoded, so the original text must be checked instead.
+                let mut name = checkpoint;
+                name.try_consume_byte(b'*');
+                if options.validate_property_names && !name.consume_name().is_ok_and(is_ident) {
+                    warn!(options.warn_handler,
+                          "'{}' is not a valid property name. Skipped.", declaration.name);
+                    let pos = s.gen_text_pos_from(start);
+                    diagnostics.push((pos, Error::InvalidIdent(pos)));
+                    continue;
+                }
+
+                declarations.push(declaration);
+            }
+            Err(e) => {
+                warn!(options.warn_handler, "Declaration parsing failed cause {}.", e);
+                diagnostics.push((s.gen_text_pos_from(start), e));
+                *s = checkpoint;
+                skip_declaration(s);
             }
         }
     }
@@ -358,6 +2326,38 @@ fn consume_declarations<'a>(s: &mut Stream<'a>) -> Result<Vec<Declaration<'a>>,
     Ok(declarations)
 }
 
+// Skips an invalid declaration up to and including the next `;`
+// or up to the end of the current block.
+fn skip_declaration(s: &mut Stream) {
+    let mut braces = 0;
+    while !s.at_end() {
+        match s.curr_byte_unchecked() {
+            b'\'' | b'"' => {
+                // An unclosed string will consume the rest of the stream.
+                let _ = s.consume_string();
+                continue;
+            }
+            b'{' => {
+                braces += 1;
+            }
+            b'}' => {
+                if braces == 0 {
+                    break;
+                } else {
+                    braces -= 1;
+                }
+            }
+            b';' if braces == 0 => {
+                s.advance(1);
+                break;
+            }
+            _ => {}
+        }
+
+        s.advance(1);
+    }
+}
+
 
 /// A declaration tokenizer.
 ///
@@ -369,11 +2369,12 @@ fn consume_declarations<'a>(s: &mut Stream<'a>) -> Result<Vec<Declaration<'a>>,
 /// use simplecss::{DeclarationTokenizer, Declaration};
 ///
 /// let mut t = DeclarationTokenizer::from("background: url(\"img.png\"); color:red !important");
-/// assert_eq!(t.next().unwrap(), Declaration { name: "background", value: "url(\"img.png\")", important: false });
-/// assert_eq!(t.next().unwrap(), Declaration { name: "color", value: "red", important: true });
+/// assert_eq!(t.next().unwrap(), Declaration::new("background", "url(\"img.png\")", false));
+/// assert_eq!(t.next().unwrap(), Declaration::new("color", "red", true));
 /// ```
+#[derive(Clone)]
 pub struct DeclarationTokenizer<'a> {
-    stream: Stream<'a>,
+    stream: Stream<'a, 'static>,
 }
 
 impl<'a> From<&'a str> for DeclarationTokenizer<'a> {
@@ -384,6 +2385,20 @@ impl<'a> From<&'a str> for DeclarationTokenizer<'a> {
     }
 }
 
+impl<'a> DeclarationTokenizer<'a> {
+    /// Returns the next declaration without advancing the tokenizer.
+    pub fn peek(&self) -> Option<Declaration<'a>> {
+        self.clone().next()
+    }
+
+    /// Returns the current byte offset in the text.
+    ///
+    /// A clone can be used to save the tokenizer state and restore it later.
+    pub fn pos(&self) -> usize {
+        self.stream.pos()
+    }
+}
+
 impl<'a> Iterator for DeclarationTokenizer<'a> {
     type Item = Declaration<'a>;
 
@@ -404,7 +2419,166 @@ impl<'a> Iterator for DeclarationTokenizer<'a> {
     }
 }
 
-fn consume_declaration<'a>(s: &mut Stream<'a>) -> Result<Declaration<'a>, Error> {
+impl<'a> FusedIterator for DeclarationTokenizer<'a> {}
+
+/// A declaration value token.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum ValueToken<'a> {
+    /// `url(img.png)` or `url("img.png")`
+    ///
+    /// Contains an URL without quotes, surrounding whitespaces and escapes.
+    /// The second field indicates that the URL was quoted.
+    Url(Cow<'a, str>, bool),
+
+    /// Any other term, like `5px`, `red`, `#fff`, `"text"`, `,` or `rgb(0, 0, 0)`.
+    ///
+    /// Contains an unprocessed text.
+    Term(&'a str),
+}
+
+
+/// A declaration value tokenizer.
+///
+/// Tokenizer will stop at the first invalid token.
+///
+/// # Example
+///
+/// ```
+/// use simplecss::{ValueTokenizer, ValueToken};
+///
+/// let mut t = ValueTokenizer::from("url( 'img.png' ) no-repeat");
+/// assert_eq!(t.next().unwrap(), ValueToken::Url("img.png".into(), true));
+/// assert_eq!(t.next().unwrap(), ValueToken::Term("no-repeat"));
+/// assert!(t.next().is_none());
+/// ```
+#[derive(Clone)]
+pub struct ValueTokenizer<'a> {
+    stream: Stream<'a, 'static>,
+}
+
+impl<'a> From<&'a str> for ValueTokenizer<'a> {
+    fn from(text: &'a str) -> Self {
+        ValueTokenizer {
+            stream: Stream::from(text),
+        }
+    }
+}
+
+impl<'a> ValueTokenizer<'a> {
+    /// Returns the next token without advancing the tokenizer.
+    pub fn peek(&self) -> Option<ValueToken<'a>> {
+        self.clone().next()
+    }
+
+    /// Returns the current byte offset in the text.
+    ///
+    /// A clone can be used to save the tokenizer state and restore it later.
+    pub fn pos(&self) -> usize {
+        self.stream.pos()
+    }
+
+    /// Returns the next token with its byte range in the text.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use simplecss::{ValueTokenizer, ValueToken};
+    ///
+    /// let mut t = ValueTokenizer::from("1px /**/ url(img.png)");
+    /// assert_eq!(t.next_spanned().unwrap(), (ValueToken::Term("1px"), 0..3));
+    /// assert_eq!(t.next_spanned().unwrap(), (ValueToken::Url("img.png".into(), false), 9..21));
+    /// assert!(t.next_spanned().is_none());
+    /// ```
+    pub fn next_spanned(&mut self) -> Option<(ValueToken<'a>, Range<usize>)> {
+        let _ = self.stream.skip_spaces_and_comments();
+
+        if self.stream.at_end() {
+            return None;
+        }
+
+        let start = self.stream.pos();
+        let token = if is_url_start(self.stream.slice_tail()) {
+            consume_url(&mut self.stream).map(|(url, quoted)| ValueToken::Url(unescape(url), quoted))
+        } else {
+            consume_term(&mut self.stream).map(|_| ValueToken::Term(self.stream.slice_back(start)))
+        };
+
+        match token {
+            Ok(v) => Some((v, start..self.stream.pos())),
+            Err(_) => {
+                self.stream.jump_to_end();
+                None
+            }
+        }
+    }
+}
+
+impl<'a> Iterator for ValueTokenizer<'a> {
+    type Item = ValueToken<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_spanned().map(|(token, _)| token)
+    }
+}
+
+impl<'a> FusedIterator for ValueTokenizer<'a> {}
+
+fn is_url_start(text: &str) -> bool {
+    text.get(0..4).map(|v| v.eq_ignore_ascii_case("url(")) == Some(true)
+}
+
+fn consume_url<'a>(s: &mut Stream<'a, '_>) -> Result<(&'a str, bool), Error> {
+    s.advance(4); // url(
+    s.skip_spaces();
+
+    let (url, quoted) = match s.curr_byte()? {
+        b'\'' | b'"' => (s.consume_string()?, true),
+        _ => {
+            // An unquoted URL cannot contain whitespaces, but can contain escapes.
+            let start = s.pos();
+            while let Ok(c) = s.curr_byte() {
+                match c {
+                    b')' | b' ' | b'\t' | b'\n' | b'\r' | b'\x0C' => break,
+                    b'\\' => {
+                        if !s.try_consume_escape() {
+                            return Err(Error::InvalidValue(s.gen_text_pos()));
+                        }
+                    }
+                    b'\'' | b'"' | b'(' => return Err(Error::InvalidValue(s.gen_text_pos())),
+                    _ => s.advance(1),
+                }
+            }
+
+            (s.slice_back(start), false)
+        }
+    };
+
+    s.skip_spaces();
+    s.consume_byte(b')')?;
+    Ok((url, quoted))
+}
+
+/// Parses declarations from an inline style, like the `style` attribute in HTML and SVG.
+///
+/// Parsing will be stopped at the first invalid declaration,
+/// but all the declarations before it will be returned.
+///
+/// # Example
+///
+/// ```
+/// use simplecss::{parse_inline_declarations, Declaration};
+///
+/// let declarations = parse_inline_declarations("fill:red; stroke: blue !important; 5");
+/// assert_eq!(declarations, vec![
+///     Declaration::new("fill", "red", false),
+///     Declaration::new("stroke", "blue", true),
+/// ]);
+/// ```
+pub fn parse_inline_declarations(text: &str) -> Vec<Declaration<'_>> {
+    DeclarationTokenizer::from(text).collect()
+}
+
+fn consume_declaration<'a>(s: &mut Stream<'a, '_>) -> Result<Declaration<'a>, Error> {
     s.skip_spaces_and_comments()?;
 
     // Parse name.
@@ -414,10 +2588,11 @@ fn consume_declaration<'a>(s: &mut Stream<'a>) -> Result<Declaration<'a>, Error>
         s.advance(1);
     }
 
-    let name = s.consume_ident()?;
+    let name = s.consume_name()?;
 
     s.skip_spaces_and_comments()?;
     s.consume_byte(b':')?;
+    let raw_start = s.pos();
     s.skip_spaces_and_comments()?;
 
     // Parse value.
@@ -430,32 +2605,225 @@ fn consume_declaration<'a>(s: &mut Stream<'a>) -> Result<Declaration<'a>, Error>
     let value = s.slice_range(start, end).trim();
 
     s.skip_spaces_and_comments()?;
+    let raw_value = s.slice_back(raw_start);
 
     // Check for `important`.
     let mut important = false;
     if s.curr_byte() == Ok(b'!') {
+        let bang = s.pos();
         s.advance(1);
         s.skip_spaces_and_comments()?;
-        if s.slice_tail().starts_with("important") {
-            s.advance(9);
-            important = true;
+        // Case-insensitive, like `!IMPORTANT`, but not a prefix, like `!importantly`.
+        // Any other `!` suffix invalidates the whole declaration.
+        if s.consume_ident().map(|v| v.eq_ignore_ascii_case("important")) != Ok(true) {
+            return Err(Error::InvalidValue(s.gen_text_pos_from(bang)));
         }
+        important = true;
     }
 
     s.skip_spaces_and_comments()?;
 
+    if value.is_empty() {
+        return Err(Error::InvalidValue(s.gen_text_pos_from(start)));
+    }
+
+    // The declaration is complete after a `;`, so an unclosed comment after it
+    // is left for `consume_declarations`.
     while s.curr_byte() == Ok(b';') {
         s.advance(1);
-        s.skip_spaces_and_comments()?;
+        let mut s2 = *s;
+        if s2.skip_spaces_and_comments().is_err() {
+            break;
+        }
+        *s = s2;
     }
 
-    s.skip_spaces_and_comments()?;
+    Ok(Declaration { name: unescape(name), value, important, raw_value })
+}
 
-    if value.is_empty() {
-        return Err(Error::InvalidValue(s.gen_text_pos_from(start)));
+/// Decodes CSS escapes, like `\26` or `\"`.
+///
+/// Identifiers and strings produced by the parser and tokenizers are borrowed from
+/// the original text as is, therefore they may contain escapes.
+/// Except `Declaration::name`, `SelectorToken::ClassSelector` and `SelectorToken::IdSelector`,
+/// which are already decoded.
+///
+/// Allocates only when the text has escapes.
+///
+/// # Example
+///
+/// ```
+/// use simplecss::unescape;
+///
+/// assert_eq!(unescape("\\26 B"), "&B");
+/// assert_eq!(unescape("a\\\"b"), "a\"b");
+/// assert_eq!(unescape("plain"), "plain");
+/// ```
+pub fn unescape(text: &str) -> Cow<'_, str> {
+    if !text.contains('\\') {
+        return Cow::Borrowed(text);
+    }
+
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+
+        match chars.peek() {
+            Some(c) if c.is_ascii_hexdigit() => {
+                let mut code = 0;
+                let mut len = 0;
+                while let Some(d) = chars.peek().and_then(|c| c.to_digit(16)) {
+                    if len == 6 {
+                        break;
+                    }
+
+                    code = code * 16 + d;
+                    len += 1;
+                    chars.next();
+                }
+
+                // A single whitespace after a hex escape is a part of it.
+                match chars.peek() {
+                    Some('\r') => {
+                        chars.next();
+                        if chars.peek() == Some(&'\n') {
+                            chars.next();
+                        }
+                    }
+                    Some(' ') | Some('\t') | Some('\n') | Some('\x0C') => {
+                        chars.next();
+                    }
+                    _ => {}
+                }
+
+                let c = match code {
+                    0 => '\u{FFFD}',
+                    _ => core::char::from_u32(code).unwrap_or('\u{FFFD}'),
+                };
+                out.push(c);
+            }
+            Some('\n') | Some('\x0C') => {
+                // An escaped newline is a line continuation.
+                chars.next();
+            }
+            Some('\r') => {
+                chars.next();
+                if chars.peek() == Some(&'\n') {
+                    chars.next();
+                }
+            }
+            Some(_) => {
+                out.extend(chars.next());
+            }
+            None => {}
+        }
+    }
+
+    Cow::Owned(out)
+}
+
+fn is_ident(text: &str) -> bool {
+    let mut s = Stream::from(text);
+    s.consume_ident().is_ok() && s.at_end()
+}
+
+/// Splits a trailing `!important` from a declaration value.
+///
+/// Spaces between `!` and `important` are allowed and `important` is case-insensitive.
+/// The returned value is trimmed from the right.
+///
+/// Useful when a value was extracted without the `DeclarationTokenizer`.
+///
+/// # Example
+///
+/// ```
+/// use simplecss::split_important;
+///
+/// assert_eq!(split_important("red ! IMPORTANT "), ("red", true));
+/// assert_eq!(split_important("red"), ("red", false));
+/// ```
+pub fn split_important(value: &str) -> (&str, bool) {
+    let text = value.trim_end();
+
+    let len = text.len();
+    let ident = if len >= 9 { text.get(len - 9..) } else { None };
+    if ident.map(|v| v.eq_ignore_ascii_case("important")) == Some(true) {
+        let text = text[..len - 9].trim_end();
+        if let Some(text) = text.strip_suffix('!') {
+            return (text.trim_end(), true);
+        }
+    }
+
+    (value.trim_end(), false)
+}
+
+/// Splits a declaration value into terms.
+///
+/// Yields numbers with units, identifiers, strings, commas and functions.
+/// Functions, including `url()`, are returned as a whole. Comments are skipped.
+///
+/// Iteration will be stopped at the first invalid term.
+///
+/// # Example
+///
+/// ```
+/// use simplecss::value_tokens;
+///
+/// let terms: Vec<_> = value_tokens("1px solid rgb(1, 2, 3), 'a b'").collect();
+/// assert_eq!(terms, vec!["1px", "solid", "rgb(1, 2, 3)", ",", "'a b'"]);
+/// ```
+pub fn value_tokens(value: &str) -> impl Iterator<Item = &str> {
+    let mut s = Stream::from(value);
+    core::iter::from_fn(move || {
+        if s.skip_spaces_and_comments().is_err() || s.at_end() {
+            return None;
+        }
+
+        let start = s.pos();
+        match consume_term(&mut s) {
+            Ok(_) => Some(s.slice_back(start)),
+            Err(_) => {
+                s.jump_to_end();
+                None
+            }
+        }
+    })
+}
+
+/// Extracts a URL from a `url()` value.
+///
+/// Quotes and whitespaces around the URL are removed.
+/// Escapes are preserved. Use `unescape` to decode them.
+///
+/// Returns `None` when the value is not a single `url()` term.
+///
+/// # Example
+///
+/// ```
+/// use simplecss::parse_url;
+///
+/// assert_eq!(parse_url("url(img.png)"), Some("img.png"));
+/// assert_eq!(parse_url(" url( 'img.png' ) "), Some("img.png"));
+/// assert_eq!(parse_url("url(img.png) no-repeat"), None);
+/// assert_eq!(parse_url("'img.png'"), None);
+/// ```
+pub fn parse_url(value: &str) -> Option<&str> {
+    let text = value.trim();
+    if !is_url_start(text) {
+        return None;
+    }
+
+    let mut s = Stream::from(text);
+    let (url, _) = consume_url(&mut s).ok()?;
+    if !s.at_end() {
+        return None;
     }
 
-    Ok(Declaration { name, value, important })
+    Some(url)
 }
 
 fn consume_term(s: &mut Stream) -> Result<(), Error> {
@@ -509,9 +2877,38 @@ fn consume_term(s: &mut Stream) -> Result<(), Error> {
 
             // Consume function.
             if s.curr_byte() == Ok(b'(') {
-                s.skip_bytes(|c| c != b')');
-                s.consume_byte(b')')?;
+                consume_function_args(s)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn consume_function_args(s: &mut Stream) -> Result<(), Error> {
+    // Functions can be nested, like in `var(--a, var(--b))`,
+    // so we have to check for matching parentheses.
+    s.consume_byte(b'(')?;
+
+    let mut depth = 0;
+    loop {
+        match s.curr_byte()? {
+            b'(' => {
+                depth += 1;
+                s.advance(1);
+            }
+            b')' => {
+                s.advance(1);
+                if depth == 0 {
+                    break;
+                }
+
+                depth -= 1;
+            }
+            b'\'' | b'"' => {
+                s.consume_string()?;
             }
+            _ => s.advance(1),
         }
     }
 
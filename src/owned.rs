@@ -0,0 +1,64 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::{Origin, StyleSheet};
+
+/// A style sheet that owns its text.
+///
+/// Unlike `StyleSheet`, doesn't borrow anything, so it can outlive the text it was parsed from.
+/// `sheet` parses the stored text on each call, so its result should be reused when possible.
+///
+/// # Example
+///
+/// ```
+/// use simplecss::{OwnedStyleSheet, StyleSheet};
+///
+/// fn load() -> OwnedStyleSheet {
+///     let text = String::from("a { color:red } b { color:green }");
+///     StyleSheet::parse(&text).into_owned()
+/// }
+///
+/// let owned = load();
+/// assert_eq!(owned.sheet().rules.len(), 2);
+/// ```
+#[derive(Clone, Debug)]
+pub struct OwnedStyleSheet {
+    pub(crate) text: String,
+    // Rules origins and source order that cannot be stored in the text.
+    pub(crate) rules: Vec<(Origin, usize)>,
+}
+
+impl OwnedStyleSheet {
+    /// Creates a style sheet from an owned text.
+    pub fn new(text: String) -> Self {
+        OwnedStyleSheet {
+            text,
+            rules: Vec::new(),
+        }
+    }
+
+    /// Returns the stored text.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Parses the stored text.
+    ///
+    /// Rules origins and source order are restored when the style sheet
+    /// was created via `StyleSheet::into_owned`.
+    pub fn sheet(&self) -> StyleSheet<'_> {
+        let mut sheet = StyleSheet::parse(&self.text);
+        for (rule, &(origin, source_order)) in sheet.rules.iter_mut().zip(&self.rules) {
+            rule.origin = origin;
+            rule.source_order = source_order;
+        }
+
+        sheet
+    }
+}
+
+impl From<String> for OwnedStyleSheet {
+    fn from(text: String) -> Self {
+        OwnedStyleSheet::new(text)
+    }
+}
@@ -1,13 +1,18 @@
-use std::fmt;
-
-use log::warn;
+use alloc::borrow::Cow;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt;
+use core::iter::FusedIterator;
+use core::ops::Range;
 
 use crate::stream::Stream;
-use crate::Error;
+use crate::{unescape, Error, ParseOptions, TextPos};
 
 
 /// An attribute selector operator.
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AttributeOperator<'a> {
     /// `[attr]`
     Exists,
@@ -22,21 +27,38 @@ pub enum AttributeOperator<'a> {
 impl<'a> AttributeOperator<'a> {
     /// Checks that value is matching the operator.
     pub fn matches(&self, value: &str) -> bool {
+        self.matches_impl(value, |a, b| a == b)
+    }
+
+    /// Checks that value is matching the operator, ignoring ASCII case.
+    ///
+    /// Used by attribute selectors with the `i` flag, like `[type=submit i]`.
+    pub fn matches_ignore_case(&self, value: &str) -> bool {
+        self.matches_impl(value, |a, b| a.eq_ignore_ascii_case(b))
+    }
+
+    fn matches_impl(&self, value: &str, eq: impl Fn(&str, &str) -> bool) -> bool {
         match *self {
             AttributeOperator::Exists => {
                 true
             }
             AttributeOperator::Matches(v) => {
-                value == v
+                eq(value, v)
             }
             AttributeOperator::Contains(v) => {
-                value.split(' ').any(|s| s == v)
+                // A whitespace-separated list of words.
+                // An empty word or a word with whitespaces never matches.
+                if v.is_empty() || v.contains(|c: char| c.is_ascii_whitespace()) {
+                    return false;
+                }
+
+                value.split_ascii_whitespace().any(|s| eq(s, v))
             }
             AttributeOperator::StartsWith(v) => {
                 // exactly `v` or beginning with `v` immediately followed by `-`
-                if value == v {
+                if eq(value, v) {
                     true
-                } else if value.starts_with(v) {
+                } else if value.get(..v.len()).map(|prefix| eq(prefix, v)) == Some(true) {
                     value.get(v.len()..v.len()+1) == Some("-")
                 } else {
                     false
@@ -48,7 +70,8 @@ impl<'a> AttributeOperator<'a> {
 
 
 /// A pseudo-class.
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(missing_docs)]
 pub enum PseudoClass<'a> {
     FirstChild,
@@ -57,7 +80,20 @@ pub enum PseudoClass<'a> {
     Hover,
     Active,
     Focus,
+    /// `:lang(en)`
+    ///
+    /// Matched via `Element::language_matches`.
     Lang(&'a str),
+    /// `:nth-child(an+b)` stored as `(a, b)`.
+    NthChild(i32, i32),
+    /// `:scope`
+    ///
+    /// Since there is no way to specify a scoping root, matches the root element, like `:root`.
+    Scope,
+    /// `:root`
+    ///
+    /// Matches an element without a parent element.
+    Root,
 }
 
 impl fmt::Display for PseudoClass<'_> {
@@ -70,6 +106,17 @@ impl fmt::Display for PseudoClass<'_> {
             PseudoClass::Active => write!(f, "active"),
             PseudoClass::Focus => write!(f, "focus"),
             PseudoClass::Lang(lang) => write!(f, "lang({})", lang),
+            PseudoClass::Scope => write!(f, "scope"),
+            PseudoClass::Root => write!(f, "root"),
+            PseudoClass::NthChild(a, b) => {
+                if *a == 0 {
+                    write!(f, "nth-child({})", b)
+                } else if *b == 0 {
+                    write!(f, "nth-child({}n)", a)
+                } else {
+                    write!(f, "nth-child({}n{:+})", a, b)
+                }
+            }
         }
     }
 }
@@ -81,61 +128,341 @@ pub trait Element: Sized {
     fn parent_element(&self) -> Option<Self>;
 
     /// Returns a previous sibling element.
+    ///
+    /// Trees that cannot go backwards can return `None`
+    /// and implement `children` and `is_same` instead.
     fn prev_sibling_element(&self) -> Option<Self>;
 
+    /// Returns child elements.
+    ///
+    /// This method is used by the selector matching to find element siblings
+    /// for `:nth-child` and the `+` combinator by scanning the parent element children.
+    /// Useful for trees that can iterate children only forward.
+    /// Requires `is_same` to be implemented as well.
+    ///
+    /// The default implementation returns `None`, so `prev_sibling_element` will be used.
+    fn children(&self) -> Option<Vec<Self>> {
+        None
+    }
+
+    /// Checks that both elements represent the same node.
+    ///
+    /// Used together with `children`.
+    ///
+    /// The default implementation returns `false`.
+    fn is_same(&self, other: &Self) -> bool {
+        let _ = other;
+        false
+    }
+
     /// Checks that the element has a specified local name.
     fn has_local_name(&self, name: &str) -> bool;
 
+    /// Checks that the element has a specified local name.
+    ///
+    /// This method is used by the selector matching. Byte-oriented implementations
+    /// can override it to avoid UTF-8 conversions.
+    ///
+    /// The default implementation delegates to `has_local_name`.
+    fn has_local_name_bytes(&self, name: &[u8]) -> bool {
+        match core::str::from_utf8(name) {
+            Ok(name) => self.has_local_name(name),
+            Err(_) => false,
+        }
+    }
+
+    /// Checks that the element belongs to a namespace with a specified prefix.
+    ///
+    /// This method is used by the selector matching for prefixed selectors, like `svg|rect`.
+    /// An empty prefix, like in `|rect`, means that the element must not have a namespace.
+    /// Prefixes are declared via `@namespace` rules. Use `StyleSheet::namespace_uri`
+    /// to resolve them.
+    ///
+    /// The default implementation returns `true`, i.e. namespaces are ignored.
+    fn has_namespace_prefix(&self, prefix: &str) -> bool {
+        let _ = prefix;
+        true
+    }
+
     /// Checks that the element has a specified attribute.
     fn attribute_matches(&self, local_name: &str, operator: AttributeOperator) -> bool;
 
+    /// Checks that the element has a specified attribute,
+    /// comparing values ASCII case-insensitively.
+    ///
+    /// This method is used by the selector matching for attribute selectors
+    /// with the `i` flag, like `[type=submit i]`.
+    /// Implementations can use `AttributeOperator::matches_ignore_case`.
+    ///
+    /// The default implementation delegates to `attribute_matches`,
+    /// i.e. values are compared case-sensitively.
+    fn attribute_matches_ignore_case(&self, local_name: &str, operator: AttributeOperator) -> bool {
+        self.attribute_matches(local_name, operator)
+    }
+
+    /// Checks that the element has a specified class.
+    ///
+    /// This method is used by the selector matching for `.class` and `[class~=value]`.
+    /// Implementations that store classes separately, like in a `HashSet`,
+    /// can override it to avoid parsing the `class` attribute.
+    ///
+    /// The default implementation delegates to `attribute_matches`.
+    fn has_class(&self, class: &str) -> bool {
+        self.attribute_matches("class", AttributeOperator::Contains(class))
+    }
+
     /// Checks that the element matches a specified pseudo-class.
     fn pseudo_class_matches(&self, class: PseudoClass) -> bool;
+
+    /// Checks that the element language matches a specified language range, like `en`.
+    ///
+    /// This method is used by the selector matching for `:lang()`.
+    /// A range matches a language that is equal to it or starts with it followed by `-`,
+    /// i.e. `en` matches `en-US`.
+    ///
+    /// The default implementation uses the `lang` attribute of the element or of the closest
+    /// ancestor that has one. Values are compared via `attribute_matches_ignore_case`.
+    fn language_matches(&self, range: &str) -> bool {
+        let operator = AttributeOperator::StartsWith(range);
+        if self.attribute_matches("lang", AttributeOperator::Exists) {
+            return self.attribute_matches_ignore_case("lang", operator);
+        }
+
+        let mut parent = self.parent_element();
+        while let Some(element) = parent {
+            if element.attribute_matches("lang", AttributeOperator::Exists) {
+                return element.attribute_matches_ignore_case("lang", operator);
+            }
+
+            parent = element.parent_element();
+        }
+
+        false
+    }
+
+    /// Returns a pseudo-element name that this element represents, like `before`.
+    ///
+    /// Selectors with a pseudo-element will match only elements that return the same name.
+    /// And selectors without a pseudo-element will match only elements that return `None`.
+    /// Everything else, like a local name or attributes, must be queried
+    /// from the originating element.
+    ///
+    /// The default implementation returns `None`.
+    fn pseudo_element(&self) -> Option<&str> {
+        None
+    }
 }
 
 
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 enum SimpleSelectorType<'a> {
     Type(&'a str),
     Universal,
 }
 
 
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 enum SubSelector<'a> {
-    Attribute(&'a str, AttributeOperator<'a>),
-    PseudoClass(PseudoClass<'a>),
+    /// `#id`, which is stored separately from `[id=id]`, because it has a higher specificity.
+    Id(&'a str),
+    /// The last field indicates the `i` flag.
+    Attribute(&'a str, #[cfg_attr(feature = "serde", serde(borrow))] AttributeOperator<'a>, bool),
+    PseudoClass(#[cfg_attr(feature = "serde", serde(borrow))] PseudoClass<'a>),
+    /// `:not()` with a single simple selector.
+    Not(#[cfg_attr(feature = "serde", serde(borrow))] SimpleSelector<'a>),
+    /// `:is()` with a list of compound selectors.
+    Is(#[cfg_attr(feature = "serde", serde(borrow))] Vec<SimpleSelector<'a>>),
+    /// `:where()` with a list of compound selectors.
+    Where(#[cfg_attr(feature = "serde", serde(borrow))] Vec<SimpleSelector<'a>>),
 }
 
 
-#[derive(Clone, Debug)]
-struct SimpleSelector<'a> {
+/// A compound selector without combinators, like `p.warning:first-child`.
+///
+/// Can be accessed via `Selector::components`.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SimpleSelector<'a> {
+    #[cfg_attr(feature = "serde", serde(borrow, default))]
+    namespace: Option<&'a str>,
+    #[cfg_attr(feature = "serde", serde(borrow))]
     kind: SimpleSelectorType<'a>,
+    #[cfg_attr(feature = "serde", serde(borrow))]
     subselectors: Vec<SubSelector<'a>>,
 }
 
+impl<'a> SimpleSelector<'a> {
+    /// Returns a type selector name.
+    ///
+    /// Returns `None` for the universal selector (`*`) or when the type selector is omitted.
+    /// The name is stored as is, without unescaping.
+    pub fn local_name(&self) -> Option<&'a str> {
+        match self.kind {
+            SimpleSelectorType::Type(name) => Some(name),
+            SimpleSelectorType::Universal => None,
+        }
+    }
 
-#[derive(Clone, Copy, PartialEq, Debug)]
-enum Combinator {
-    None,
+    /// Returns a namespace prefix of a type or universal selector.
+    ///
+    /// Like `svg` for `svg|rect`, `*` for `*|rect` and an empty string for `|rect`.
+    /// Returns `None` when the prefix is omitted.
+    pub fn namespace_prefix(&self) -> Option<&'a str> {
+        self.namespace
+    }
+
+    /// Returns an iterator over ID selectors, like `a` for `#a`.
+    ///
+    /// Unlike classes, `[id=a]` is not returned, since it's an attribute selector
+    /// with a different specificity.
+    /// IDs are stored as is, without unescaping.
+    pub fn ids(&self) -> impl Iterator<Item = &'a str> + '_ {
+        self.subselectors.iter().filter_map(|sub| match *sub {
+            SubSelector::Id(id) => Some(id),
+            _ => None,
+        })
+    }
+
+    /// Returns an iterator over class selectors, like `a` for `.a`.
+    ///
+    /// Since `.a` is the same as `[class~=a]`, the latter will be returned too.
+    /// Classes are stored as is, without unescaping.
+    pub fn classes(&self) -> impl Iterator<Item = &'a str> + '_ {
+        self.subselectors.iter().filter_map(|sub| match *sub {
+            SubSelector::Attribute("class", AttributeOperator::Contains(class), false) => Some(class),
+            _ => None,
+        })
+    }
+
+    /// Returns an iterator over attribute selectors.
+    ///
+    /// Doesn't include the ones returned by `classes`.
+    pub fn attributes(&self) -> impl Iterator<Item = (&'a str, AttributeOperator<'a>)> + '_ {
+        self.subselectors.iter().filter_map(|sub| match *sub {
+            SubSelector::Attribute("class", AttributeOperator::Contains(_), false) => None,
+            SubSelector::Attribute(name, operator, _) => Some((name, operator)),
+            _ => None,
+        })
+    }
+
+    /// Returns an iterator over pseudo-classes.
+    ///
+    /// Doesn't include `:not()`, `:is()` and `:where()`. Use `negations` for `:not()`.
+    pub fn pseudo_classes(&self) -> impl Iterator<Item = PseudoClass<'a>> + '_ {
+        self.subselectors.iter().filter_map(|sub| match *sub {
+            SubSelector::PseudoClass(class) => Some(class),
+            _ => None,
+        })
+    }
+
+    /// Returns an iterator over `:not()` pseudo-classes arguments.
+    pub fn negations(&self) -> impl Iterator<Item = &SimpleSelector<'a>> + '_ {
+        self.subselectors.iter().filter_map(|sub| match sub {
+            SubSelector::Not(selector) => Some(selector),
+            _ => None,
+        })
+    }
+}
+
+
+/// A selector combinator.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Combinator {
+    /// `a b`
     Descendant,
+    /// `a > b`
     Child,
+    /// `a + b`
     AdjacentSibling,
+    /// `col || td`
+    ///
+    /// Parsed, but never matches, since table columns are not supported.
+    Column,
 }
 
 
-#[derive(Clone, Debug)]
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct Component<'a> {
     /// A combinator that precede the selector.
-    combinator: Combinator,
+    combinator: Option<Combinator>,
+    #[cfg_attr(feature = "serde", serde(borrow))]
     selector: SimpleSelector<'a>,
 }
 
 
+/// A selector specificity.
+///
+/// Stored as a number of ID selectors, a number of class selectors,
+/// attribute selectors and pseudo-classes, and a number of type selectors.
+/// The universal selector is ignored.
+///
+/// Specificities are compared lexicographically.
+/// Displayed as `a,b,c`.
+///
+/// Cf. https://www.w3.org/TR/selectors/#specificity.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Specificity(pub [u32; 3]);
+
+impl Specificity {
+    /// Returns the number of ID selectors.
+    pub fn id_count(&self) -> u32 {
+        self.0[0]
+    }
+
+    /// Returns the number of class selectors, attribute selectors and pseudo-classes.
+    pub fn class_count(&self) -> u32 {
+        self.0[1]
+    }
+
+    /// Returns the number of type selectors and pseudo-elements.
+    pub fn type_count(&self) -> u32 {
+        self.0[2]
+    }
+}
+
+impl fmt::Display for Specificity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{},{},{}", self.0[0], self.0[1], self.0[2])
+    }
+}
+
+
+/// A selector matching context.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct MatchingContext {
+    /// Compare type selectors and attribute names case-sensitively.
+    ///
+    /// When disabled, type selectors and attribute names will be converted
+    /// to ASCII lowercase before passing them to an `Element`, which is what HTML requires.
+    /// Classes, IDs and attribute values are always case-sensitive.
+    ///
+    /// Default: `true`
+    pub case_sensitive: bool,
+}
+
+impl Default for MatchingContext {
+    fn default() -> Self {
+        MatchingContext { case_sensitive: true }
+    }
+}
+
+
 /// A selector.
-#[derive(Clone, Debug)]
+///
+/// Selectors are compared structurally, so `p.a` and `p[class~=a]` are equal,
+/// while `.a.b` and `.b.a` are not.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Selector<'a> {
-    components: Vec<Component<'a>>
+    #[cfg_attr(feature = "serde", serde(borrow))]
+    components: Vec<Component<'a>>,
+    #[cfg_attr(feature = "serde", serde(borrow))]
+    pseudo_element: Option<&'a str>,
 }
 
 impl<'a> Selector<'a> {
@@ -145,52 +472,108 @@ impl<'a> Selector<'a> {
     ///
     /// Parsing will be stopped at EOF, `,` or `{`.
     pub fn parse(text: &'a str) -> Option<Self> {
-        parse(text).0
+        parse(text, ParseOptions::default()).0.ok()
+    }
+
+    /// Parses a selector from a string and returns an error with its position on failure.
+    ///
+    /// Will log any errors as a warnings.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use simplecss::{Selector, Error, TextPos};
+    ///
+    /// let err = Selector::try_parse("a > > b").unwrap_err();
+    /// assert_eq!(err, (TextPos::new(1, 5, 4), Error::UnexpectedCombinator));
+    /// ```
+    pub fn try_parse(text: &'a str) -> Result<Self, (TextPos, Error)> {
+        let (selector, offset) = parse(text, ParseOptions::default());
+        selector.map_err(|e| (Stream::from(text).gen_text_pos_from(offset), e))
+    }
+
+    /// Returns the selector's pseudo-element name, if any.
+    ///
+    /// Like `before` for `p::before`. The legacy single colon syntax
+    /// is supported for `:before`, `:after`, `:first-line` and `:first-letter`.
+    pub fn pseudo_element(&self) -> Option<&'a str> {
+        self.pseudo_element
+    }
+
+    /// Returns an iterator over compound selectors.
+    ///
+    /// Each compound selector is returned along with a combinator
+    /// that links it to the next one. The last one doesn't have a combinator.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use simplecss::{Combinator, Selector};
+    ///
+    /// let selector = Selector::parse("div > p.warning").unwrap();
+    /// let components: Vec<_> = selector.components().collect();
+    /// assert_eq!(components[0].0.local_name(), Some("div"));
+    /// assert_eq!(components[0].1, Some(Combinator::Child));
+    /// assert_eq!(components[1].0.classes().collect::<Vec<_>>(), vec!["warning"]);
+    /// assert_eq!(components[1].1, None);
+    /// ```
+    pub fn components(&self) -> impl Iterator<Item = (&SimpleSelector<'a>, Option<Combinator>)> + '_ {
+        // A combinator is stored in the component that it precedes.
+        let next = self.components.iter().skip(1).map(|c| c.combinator).chain(core::iter::once(None));
+        self.components.iter().map(|c| &c.selector).zip(next)
     }
 
     /// Compute the selector's specificity.
     ///
     /// Cf. https://www.w3.org/TR/selectors/#specificity.
-    pub fn specificity(&self) -> [u8; 3] {
-        let mut spec = [0u8; 3];
+    pub fn specificity(&self) -> Specificity {
+        let mut spec = [0; 3];
 
         for selector in self.components.iter().map(|c| &c.selector) {
-            if matches!(selector.kind, SimpleSelectorType::Type(_)) {
-                spec[2] = spec[2].saturating_add(1);
-            }
+            add_specificity(selector, &mut spec);
+        }
 
-            for sub in &selector.subselectors {
-                match sub {
-                    SubSelector::Attribute("id", _) => spec[0] = spec[0].saturating_add(1),
-                    _ => spec[1] = spec[1].saturating_add(1),
-                }
-            }
+        if self.pseudo_element.is_some() {
+            spec[2] = spec[2].saturating_add(1);
         }
 
-        spec
+        Specificity(spec)
     }
 
     /// Checks that the provided element matches the current selector.
+    ///
+    /// Selectors with a pseudo-element match only when `Element::pseudo_element`
+    /// returns the same name.
     pub fn matches<E: Element>(&self, element: &E) -> bool {
+        self.matches_with_context(element, &MatchingContext::default())
+    }
+
+    /// Checks that the provided element matches the current selector
+    /// using the specified context.
+    pub fn matches_with_context<E: Element>(&self, element: &E, ctx: &MatchingContext) -> bool {
         assert!(!self.components.is_empty(), "selector must not be empty");
-        assert_eq!(self.components[0].combinator, Combinator::None,
+        assert_eq!(self.components[0].combinator, None,
                    "the first component must not have a combinator");
 
-        self.matches_impl(self.components.len() - 1, element)
+        if self.pseudo_element != element.pseudo_element() {
+            return false;
+        }
+
+        self.matches_impl(self.components.len() - 1, element, ctx)
     }
 
-    fn matches_impl<E: Element>(&self, idx: usize, element: &E) -> bool {
+    fn matches_impl<E: Element>(&self, idx: usize, element: &E, ctx: &MatchingContext) -> bool {
         let component = &self.components[idx];
 
-        if !match_selector(&component.selector, element) {
+        if !match_selector(&component.selector, element, ctx) {
             return false;
         }
 
         match component.combinator {
-            Combinator::Descendant => {
+            Some(Combinator::Descendant) => {
                 let mut parent = element.parent_element();
                 while let Some(e) = parent {
-                    if self.matches_impl(idx - 1, &e) {
+                    if self.matches_impl(idx - 1, &e, ctx) {
                         return true;
                     }
 
@@ -199,42 +582,136 @@ impl<'a> Selector<'a> {
 
                 false
             }
-            Combinator::Child => {
+            Some(Combinator::Child) => {
                 if let Some(parent) = element.parent_element() {
-                    if self.matches_impl(idx - 1, &parent) {
+                    if self.matches_impl(idx - 1, &parent, ctx) {
                         return true;
                     }
                 }
 
                 false
             }
-            Combinator::AdjacentSibling => {
-                if let Some(prev) = element.prev_sibling_element() {
-                    if self.matches_impl(idx - 1, &prev) {
+            Some(Combinator::AdjacentSibling) => {
+                if let Some(prev) = prev_sibling(element) {
+                    if self.matches_impl(idx - 1, &prev, ctx) {
                         return true;
                     }
                 }
 
                 false
             }
-            Combinator::None => {
+            Some(Combinator::Column) => {
+                false
+            }
+            None => {
                 true
             }
         }
     }
 }
 
-fn match_selector<E: Element>(selector: &SimpleSelector, element: &E) -> bool {
+/// Parses a standalone selector.
+///
+/// Unlike `Selector::parse`, fails on any trailing data, like `,` or `{`.
+/// `FromStr` cannot be implemented, because a selector borrows the input string.
+///
+/// # Example
+///
+/// ```
+/// use std::convert::{TryFrom, TryInto};
+/// use simplecss::{Selector, Error, TextPos};
+///
+/// let selector: Selector = ".btn".try_into().unwrap();
+/// assert_eq!(selector, Selector::parse(".btn").unwrap());
+///
+/// let err = Selector::try_from("a, b").unwrap_err();
+/// assert_eq!(err, Error::UnexpectedData(TextPos::new(1, 2, 1)));
+/// ```
+impl<'a> TryFrom<&'a str> for Selector<'a> {
+    type Error = Error;
+
+    fn try_from(text: &'a str) -> Result<Self, Self::Error> {
+        let (selector, offset) = parse(text, ParseOptions::default());
+        let selector = selector?;
+        if !text[offset..].trim().is_empty() {
+            return Err(Error::UnexpectedData(Stream::from(text).gen_text_pos_from(offset)));
+        }
+
+        Ok(selector)
+    }
+}
+
+fn add_specificity(selector: &SimpleSelector, spec: &mut [u32; 3]) {
+    if matches!(selector.kind, SimpleSelectorType::Type(_)) {
+        spec[2] = spec[2].saturating_add(1);
+    }
+
+    for sub in &selector.subselectors {
+        match sub {
+            SubSelector::Id(_) => spec[0] = spec[0].saturating_add(1),
+            // `:not()` itself doesn't count, but its argument does.
+            SubSelector::Not(selector) => add_specificity(selector, spec),
+            // `:is()` counts as its most specific argument.
+            SubSelector::Is(selectors) => {
+                let max = selectors.iter().map(|selector| {
+                    let mut spec = [0; 3];
+                    add_specificity(selector, &mut spec);
+                    spec
+                }).max().unwrap_or_default();
+
+                for (a, b) in spec.iter_mut().zip(max.iter()) {
+                    *a = a.saturating_add(*b);
+                }
+            }
+            // `:where()` never counts.
+            SubSelector::Where(_) => {}
+            _ => spec[1] = spec[1].saturating_add(1),
+        }
+    }
+}
+
+fn match_selector<E: Element>(selector: &SimpleSelector, element: &E, ctx: &MatchingContext) -> bool {
+    if let Some(prefix) = selector.namespace {
+        if prefix != "*" && !element.has_namespace_prefix(prefix) {
+            return false;
+        }
+    }
+
     if let SimpleSelectorType::Type(ident) = selector.kind {
-        if !element.has_local_name(ident) {
+        let ident = unescape(ident);
+        let ident = to_lowercase(&ident, ctx);
+        if !element.has_local_name_bytes(ident.as_bytes()) {
             return false;
         }
     }
 
     for sub in &selector.subselectors {
         match sub {
-            SubSelector::Attribute(name, operator) => {
-                if !element.attribute_matches(name, *operator) {
+            SubSelector::Id(id) => {
+                if !match_attribute(element, "id", AttributeOperator::Matches(id), false) {
+                    return false;
+                }
+            }
+            SubSelector::Attribute(name, operator, ignore_case) => {
+                let name = unescape(name);
+                if !match_attribute(element, &to_lowercase(&name, ctx), *operator, *ignore_case) {
+                    return false;
+                }
+            }
+            SubSelector::PseudoClass(PseudoClass::NthChild(a, b)) => {
+                if !match_nth_child(*a, *b, element) {
+                    return false;
+                }
+            }
+            SubSelector::PseudoClass(PseudoClass::Lang(range)) => {
+                if !element.language_matches(range) {
+                    return false;
+                }
+            }
+            SubSelector::PseudoClass(PseudoClass::Root) |
+            SubSelector::PseudoClass(PseudoClass::Scope) => {
+                // No scoping root, therefore `:scope` is the same as `:root`.
+                if element.parent_element().is_some() {
                     return false;
                 }
             }
@@ -243,75 +720,291 @@ fn match_selector<E: Element>(selector: &SimpleSelector, element: &E) -> bool {
                     return false;
                 }
             }
+            SubSelector::Not(selector) => {
+                if match_selector(selector, element, ctx) {
+                    return false;
+                }
+            }
+            SubSelector::Is(selectors) | SubSelector::Where(selectors) => {
+                if !selectors.iter().any(|selector| match_selector(selector, element, ctx)) {
+                    return false;
+                }
+            }
         }
     }
 
     true
 }
 
-pub(crate) fn parse(text: &str) -> (Option<Selector>, usize) {
+fn match_attribute<E: Element>(
+    element: &E,
+    name: &str,
+    operator: AttributeOperator,
+    ignore_case: bool,
+) -> bool {
+    // Values are passed to an `Element` without escapes.
+    if ignore_case {
+        return match operator {
+            AttributeOperator::Exists => element.attribute_matches(name, operator),
+            AttributeOperator::Matches(v) => {
+                element.attribute_matches_ignore_case(name, AttributeOperator::Matches(&unescape(v)))
+            }
+            AttributeOperator::Contains(v) => {
+                element.attribute_matches_ignore_case(name, AttributeOperator::Contains(&unescape(v)))
+            }
+            AttributeOperator::StartsWith(v) => {
+                element.attribute_matches_ignore_case(name, AttributeOperator::StartsWith(&unescape(v)))
+            }
+        };
+    }
+
+    match operator {
+        AttributeOperator::Exists => {
+            element.attribute_matches(name, operator)
+        }
+        AttributeOperator::Matches(v) => {
+            element.attribute_matches(name, AttributeOperator::Matches(&unescape(v)))
+        }
+        AttributeOperator::Contains(v) if name == "class" => {
+            element.has_class(&unescape(v))
+        }
+        AttributeOperator::Contains(v) => {
+            element.attribute_matches(name, AttributeOperator::Contains(&unescape(v)))
+        }
+        AttributeOperator::StartsWith(v) => {
+            element.attribute_matches(name, AttributeOperator::StartsWith(&unescape(v)))
+        }
+    }
+}
+
+fn to_lowercase<'a>(name: &'a str, ctx: &MatchingContext) -> Cow<'a, str> {
+    if !ctx.case_sensitive && name.bytes().any(|c| c.is_ascii_uppercase()) {
+        Cow::Owned(name.to_ascii_lowercase())
+    } else {
+        Cow::Borrowed(name)
+    }
+}
+
+// Returns the element's position among the parent element children.
+fn index_in_children<E: Element>(element: &E) -> Option<(usize, Vec<E>)> {
+    let children = element.parent_element()?.children()?;
+    let index = children.iter().position(|e| e.is_same(element))?;
+    Some((index, children))
+}
+
+fn prev_sibling<E: Element>(element: &E) -> Option<E> {
+    if let Some((index, mut children)) = index_in_children(element) {
+        if index == 0 {
+            return None;
+        }
+
+        return Some(children.swap_remove(index - 1));
+    }
+
+    element.prev_sibling_element()
+}
+
+fn match_nth_child<E: Element>(a: i32, b: i32, element: &E) -> bool {
+    // Element's position among its siblings, starting from 1.
+    let index = if let Some((index, _)) = index_in_children(element) {
+        index as i64 + 1
+    } else {
+        let mut index = 1i64;
+        let mut prev = element.prev_sibling_element();
+        while let Some(e) = prev {
+            index += 1;
+            prev = e.prev_sibling_element();
+        }
+
+        index
+    };
+
+    // Check that there is a non-negative `n` so that `a*n + b == index`.
+    let (a, b) = (i64::from(a), i64::from(b));
+    if a == 0 {
+        index == b
+    } else {
+        let diff = index - b;
+        diff % a == 0 && diff / a >= 0
+    }
+}
+
+/// Parses an `an+b` expression, like `2n+1`, `odd`, `even`, `-n+3` or `5`.
+///
+/// Returns the `(a, b)` coefficients.
+///
+/// # Errors
+///
+/// Returns `Error::InvalidNthChildPseudoClass` on invalid input.
+///
+/// # Example
+///
+/// ```
+/// use simplecss::parse_nth_child;
+///
+/// assert_eq!(parse_nth_child("2n+1").unwrap(), (2, 1));
+/// assert_eq!(parse_nth_child(" even ").unwrap(), (2, 0));
+/// assert_eq!(parse_nth_child("-n + 3").unwrap(), (-1, 3));
+/// assert!(parse_nth_child("n+").is_err());
+/// ```
+pub fn parse_nth_child(text: &str) -> Result<(i32, i32), Error> {
+    fn parse_int(text: &str) -> Result<i32, Error> {
+        if text.is_empty() || !text.bytes().all(|c| c.is_ascii_digit()) {
+            return Err(Error::InvalidNthChildPseudoClass);
+        }
+
+        text.parse().map_err(|_| Error::InvalidNthChildPseudoClass)
+    }
+
+    fn parse_signed_int(text: &str) -> Result<i32, Error> {
+        if let Some(text) = text.strip_prefix('-') {
+            parse_int(text).map(|n| -n)
+        } else if let Some(text) = text.strip_prefix('+') {
+            parse_int(text)
+        } else {
+            parse_int(text)
+        }
+    }
+
+    let text = text.trim();
+
+    if text.eq_ignore_ascii_case("odd") {
+        return Ok((2, 1));
+    } else if text.eq_ignore_ascii_case("even") {
+        return Ok((2, 0));
+    }
+
+    let n_pos = match text.find(['n', 'N']) {
+        Some(pos) => pos,
+        None => return Ok((0, parse_signed_int(text)?)),
+    };
+
+    let a = match &text[..n_pos] {
+        "" | "+" => 1,
+        "-" => -1,
+        a => parse_signed_int(a)?,
+    };
+
+    let tail = text[n_pos + 1..].trim_start();
+    let b = if tail.is_empty() {
+        0
+    } else if let Some(tail) = tail.strip_prefix('+') {
+        parse_int(tail.trim_start())?
+    } else if let Some(tail) = tail.strip_prefix('-') {
+        -parse_int(tail.trim_start())?
+    } else {
+        return Err(Error::InvalidNthChildPseudoClass);
+    };
+
+    Ok((a, b))
+}
+
+// Escapes are not decoded by the tokenizer during parsing, so an ident is always borrowed.
+fn raw_ident(ident: Cow<'_, str>) -> &str {
+    match ident {
+        Cow::Borrowed(ident) => ident,
+        Cow::Owned(_) => unreachable!("selector idents must not be unescaped during parsing"),
+    }
+}
+
+pub(crate) fn parse(text: &str, options: ParseOptions) -> (Result<Selector<'_>, Error>, usize) {
+    let warn_handler = options.warn_handler;
     let mut components: Vec<Component> = Vec::new();
-    let mut combinator = Combinator::None;
+    let mut combinator = None;
+    let mut pseudo_element = None;
+    let mut namespace = None;
 
-    let mut tokenizer = SelectorTokenizer::from(text);
+    let mut tokenizer = SelectorTokenizer { unescape: false, ..SelectorTokenizer::from(text) };
     for token in &mut tokenizer {
         let mut add_sub = |sub| {
-            if combinator == Combinator::None && !components.is_empty() {
+            if combinator.is_none() && !components.is_empty() {
                 if let Some(ref mut component) = components.last_mut() {
                     component.selector.subselectors.push(sub);
                 }
             } else {
                 components.push(Component {
                     selector: SimpleSelector {
+                        namespace: None,
                         kind: SimpleSelectorType::Universal,
                         subselectors: vec![sub],
                     },
                     combinator,
                 });
 
-                combinator = Combinator::None;
+                combinator = None;
             }
         };
 
         let token = match token {
             Ok(t) => t,
             Err(e) => {
-                warn!("Selector parsing failed cause {}.", e);
-                return (None, tokenizer.stream.pos());
+                warn!(warn_handler, "Selector parsing failed cause {}.", e);
+                return (Err(e), tokenizer.stream.pos());
             }
         };
 
+        // A pseudo-element must be the last one.
+        if pseudo_element.is_some() {
+            warn!(warn_handler, "Selector parsing failed cause a pseudo-element must be the last one.");
+            return (Err(Error::UnexpectedSelector), tokenizer.stream.pos());
+        }
+
         match token {
+            SelectorToken::NamespacePrefix(prefix) => {
+                namespace = Some(prefix);
+            }
             SelectorToken::UniversalSelector => {
                 components.push(Component {
                     selector: SimpleSelector {
+                        namespace: namespace.take(),
                         kind: SimpleSelectorType::Universal,
                         subselectors: Vec::new(),
                     },
                     combinator,
                 });
 
-                combinator = Combinator::None;
+                combinator = None;
             }
             SelectorToken::TypeSelector(ident) => {
                 components.push(Component {
                     selector: SimpleSelector {
+                        namespace: namespace.take(),
                         kind: SimpleSelectorType::Type(ident),
                         subselectors: Vec::new(),
                     },
                     combinator,
                 });
 
-                combinator = Combinator::None;
+                combinator = None;
             }
             SelectorToken::ClassSelector(ident) => {
-                add_sub(SubSelector::Attribute("class", AttributeOperator::Contains(ident)));
+                add_sub(SubSelector::Attribute("class", AttributeOperator::Contains(raw_ident(ident)), false));
             }
             SelectorToken::IdSelector(id) => {
-                add_sub(SubSelector::Attribute("id", AttributeOperator::Matches(id)));
+                add_sub(SubSelector::Id(raw_ident(id)));
+            }
+            SelectorToken::AttributeSelector(name, op, _, ignore_case) => {
+                add_sub(SubSelector::Attribute(name, op, ignore_case));
             }
-            SelectorToken::AttributeSelector(name, op) => {
-                add_sub(SubSelector::Attribute(name, op));
+            SelectorToken::PseudoClass(ident @ "before") |
+            SelectorToken::PseudoClass(ident @ "after") |
+            SelectorToken::PseudoClass(ident @ "first-line") |
+            SelectorToken::PseudoClass(ident @ "first-letter") |
+            SelectorToken::PseudoElement(ident) => {
+                if combinator.is_some() || components.is_empty() {
+                    components.push(Component {
+                        selector: SimpleSelector {
+                            namespace: None,
+                            kind: SimpleSelectorType::Universal,
+                            subselectors: Vec::new(),
+                        },
+                        combinator,
+                    });
+
+                    combinator = None;
+                }
+
+                pseudo_element = Some(ident);
             }
             SelectorToken::PseudoClass(ident) => {
                 let class = match ident {
@@ -321,9 +1014,11 @@ pub(crate) fn parse(text: &str) -> (Option<Selector>, usize) {
                     "hover" => PseudoClass::Hover,
                     "active" => PseudoClass::Active,
                     "focus" => PseudoClass::Focus,
+                    "scope" => PseudoClass::Scope,
+                    "root" => PseudoClass::Root,
                     _ => {
-                        warn!("':{}' is not supported. Selector skipped.", ident);
-                        return (None, tokenizer.stream.pos());
+                        warn!(warn_handler, "':{}' is not supported. Selector skipped.", ident);
+                        return (Err(Error::UnsupportedPseudoClass), tokenizer.stream.pos());
                     }
                 };
 
@@ -335,65 +1030,267 @@ pub(crate) fn parse(text: &str) -> (Option<Selector>, usize) {
             SelectorToken::LangPseudoClass(lang) => {
                 add_sub(SubSelector::PseudoClass(PseudoClass::Lang(lang)));
             }
+            SelectorToken::NthChildPseudoClass(a, b) => {
+                add_sub(SubSelector::PseudoClass(PseudoClass::NthChild(a, b)));
+            }
+            SelectorToken::NotPseudoClass(inner) => {
+                match options.nested().and_then(|options| parse_negation(inner, options)) {
+                    Ok(selector) => add_sub(SubSelector::Not(selector)),
+                    Err(e) => return (Err(e), tokenizer.stream.pos()),
+                }
+            }
+            SelectorToken::IsPseudoClass(inner) => {
+                match options.nested().and_then(|options| parse_compound_list(inner, options)) {
+                    Ok(selectors) => add_sub(SubSelector::Is(selectors)),
+                    Err(e) => return (Err(e), tokenizer.stream.pos()),
+                }
+            }
+            SelectorToken::WherePseudoClass(inner) => {
+                match options.nested().and_then(|options| parse_compound_list(inner, options)) {
+                    Ok(selectors) => add_sub(SubSelector::Where(selectors)),
+                    Err(e) => return (Err(e), tokenizer.stream.pos()),
+                }
+            }
             SelectorToken::DescendantCombinator => {
-                combinator = Combinator::Descendant;
+                combinator = Some(Combinator::Descendant);
             }
             SelectorToken::ChildCombinator => {
-                combinator = Combinator::Child;
+                combinator = Some(Combinator::Child);
             }
             SelectorToken::AdjacentCombinator => {
-                combinator = Combinator::AdjacentSibling;
+                combinator = Some(Combinator::AdjacentSibling);
             }
+            SelectorToken::ColumnCombinator => {
+                combinator = Some(Combinator::Column);
+            }
+            SelectorToken::Comment(_) => {}
         }
     }
 
     if components.is_empty() {
-        (None, tokenizer.stream.pos())
-    } else if components[0].combinator != Combinator::None {
-        debug_assert_eq!(components[0].combinator, Combinator::None,
+        (Err(Error::SelectorMissing), tokenizer.stream.pos())
+    } else if components[0].combinator.is_some() {
+        debug_assert_eq!(components[0].combinator, None,
                          "the first component must not have a combinator");
 
-        (None, tokenizer.stream.pos())
+        (Err(Error::UnexpectedCombinator), tokenizer.stream.pos())
     } else {
-        (Some(Selector { components }), tokenizer.stream.pos())
+        (Ok(Selector { components, pseudo_element }), tokenizer.stream.pos())
     }
 }
 
+fn parse_negation(text: &str, options: ParseOptions) -> Result<SimpleSelector<'_>, Error> {
+    let warn_handler = options.warn_handler;
+    let text = text.trim();
+    let (selector, offset) = parse(text, options);
+    let selector = selector?;
+    let mut components = selector.components;
+
+    // Only a single simple selector is allowed: a type selector, a universal selector,
+    // an attribute selector, a class selector, an ID selector or a pseudo-class.
+    let is_simple = offset == text.len() && components.len() == 1
+        && selector.pseudo_element.is_none() && {
+        let selector = &components[0].selector;
+        match selector.kind {
+            SimpleSelectorType::Type(_) => selector.subselectors.is_empty(),
+            SimpleSelectorType::Universal => {
+                // An explicit universal selector, like `*.a`, is a simple selector on its own.
+                let is_explicit = selector.namespace.is_some()
+                    || matches!(SelectorTokenizer::from(text).next(), Some(Ok(SelectorToken::UniversalSelector)));
+                let max_subselectors = if is_explicit { 0 } else { 1 };
+                selector.subselectors.len() <= max_subselectors
+                    && !matches!(selector.subselectors.first(), Some(SubSelector::Not(_)))
+            }
+        }
+    };
+
+    if !is_simple {
+        warn!(warn_handler, "':not({})' must contain a single simple selector. Selector skipped.", text);
+        return Err(Error::UnexpectedSelector);
+    }
+
+    Ok(components.remove(0).selector)
+}
+
+// Parses `:is()` and `:where()` arguments, like `.a, p.b`.
+fn parse_compound_list(text: &str, options: ParseOptions) -> Result<Vec<SimpleSelector<'_>>, Error> {
+    let warn_handler = options.warn_handler;
+    let mut s = Stream::from(text);
+    let mut selectors = Vec::new();
+    loop {
+        let (selector, offset) = parse(s.slice_tail(), options);
+        s.advance(offset);
+        s.skip_spaces_and_comments()?;
+
+        let selector = selector?;
+        if selector.pseudo_element.is_some() {
+            warn!(warn_handler, "Pseudo-elements are not allowed in '{}'. Selector skipped.", text);
+            return Err(Error::UnexpectedSelector);
+        }
+
+        // Only compound selectors are supported.
+        let mut components = selector.components;
+        if components.len() != 1 {
+            warn!(warn_handler, "Combinators are not supported in '{}'. Selector skipped.", text);
+            return Err(Error::UnexpectedCombinator);
+        }
+
+        selectors.push(components.remove(0).selector);
+
+        if s.at_end() {
+            break;
+        }
+
+        s.consume_byte(b',')?;
+    }
+
+    Ok(selectors)
+}
+
+fn write_simple_selector(selector: &SimpleSelector, f: &mut fmt::Formatter) -> fmt::Result {
+    if let Some(prefix) = selector.namespace {
+        write!(f, "{}|", prefix)?;
+    }
+
+    match selector.kind {
+        SimpleSelectorType::Universal => write!(f, "*")?,
+        SimpleSelectorType::Type(ident) => write!(f, "{}", ident)?,
+    };
+
+    for sel in &selector.subselectors {
+        match sel {
+            SubSelector::Id(id) => write!(f, "#{}", id)?,
+            SubSelector::Attribute(name, operator, ignore_case) => {
+                let flag = if *ignore_case { " i" } else { "" };
+                match operator {
+                    AttributeOperator::Exists => {
+                        write!(f, "[{}]", name)?;
+                    }
+                    AttributeOperator::Matches(value) => {
+                        write!(f, "[{}='{}'{}]", name, value, flag)?;
+                    }
+                    AttributeOperator::Contains(value) => {
+                        write!(f, "[{}~='{}'{}]", name, value, flag)?;
+                    }
+                    AttributeOperator::StartsWith(value) => {
+                        write!(f, "[{}|='{}'{}]", name, value, flag)?;
+                    }
+                };
+            }
+            SubSelector::PseudoClass(class) => write!(f, ":{}", class)?,
+            SubSelector::Not(selector) => {
+                write!(f, ":not(")?;
+                write_simple_selector(selector, f)?;
+                write!(f, ")")?;
+            }
+            SubSelector::Is(selectors) | SubSelector::Where(selectors) => {
+                let name = if matches!(sel, SubSelector::Is(_)) { "is" } else { "where" };
+                write!(f, ":{}(", name)?;
+                for (i, selector) in selectors.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, "{}", if f.alternate() { "," } else { ", " })?;
+                    }
+
+                    write_simple_selector(selector, f)?;
+                }
+                write!(f, ")")?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Use the alternate flag (`{:#}`) to omit spaces around combinators.
 impl<'a> fmt::Display for Selector<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let compact = f.alternate();
+
         for component in &self.components {
             match component.combinator {
-                Combinator::Descendant => write!(f, " ")?,
-                Combinator::Child => write!(f, " > ")?,
-                Combinator::AdjacentSibling => write!(f, " + ")?,
-                Combinator::None => {}
+                Some(Combinator::Descendant) => write!(f, " ")?,
+                Some(Combinator::Child) => write!(f, "{}", if compact { ">" } else { " > " })?,
+                Some(Combinator::AdjacentSibling) => write!(f, "{}", if compact { "+" } else { " + " })?,
+                Some(Combinator::Column) => write!(f, "{}", if compact { "||" } else { " || " })?,
+                None => {}
             }
 
-            match component.selector.kind {
-                SimpleSelectorType::Universal => write!(f, "*")?,
-                SimpleSelectorType::Type(ident) => write!(f, "{}", ident)?,
-            };
+            write_simple_selector(&component.selector, f)?;
+        }
 
-            for sel in &component.selector.subselectors {
-                match sel {
-                    SubSelector::Attribute(name, operator) => {
-                        match operator {
-                            AttributeOperator::Exists => {
-                                write!(f, "[{}]", name)?;
-                            }
-                            AttributeOperator::Matches(value) => {
-                                write!(f, "[{}='{}']", name, value)?;
-                            }
-                            AttributeOperator::Contains(value) => {
-                                write!(f, "[{}~='{}']", name, value)?;
-                            }
-                            AttributeOperator::StartsWith(value) => {
-                                write!(f, "[{}|='{}']", name, value)?;
-                            }
-                        };
-                    }
-                    SubSelector::PseudoClass(class) => write!(f, ":{}", class)?,
-                }
+        if let Some(name) = self.pseudo_element {
+            write!(f, "::{}", name)?;
+        }
+
+        Ok(())
+    }
+}
+
+
+/// A comma-separated list of selectors.
+///
+/// Like `h1, h2, .warn`.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SelectorList<'a> {
+    /// A list of selectors.
+    #[cfg_attr(feature = "serde", serde(borrow))]
+    pub selectors: Vec<Selector<'a>>,
+}
+
+impl<'a> SelectorList<'a> {
+    /// Parses a selector list from a string.
+    ///
+    /// Will log any errors as a warnings.
+    ///
+    /// Parsing will be stopped at EOF or `{`.
+    /// If any of the selectors is invalid, the whole list is invalid.
+    pub fn parse(text: &'a str) -> Option<Self> {
+        parse_selector_list(text).0
+    }
+}
+
+pub(crate) fn parse_selector_list(text: &str) -> (Option<SelectorList<'_>>, usize) {
+    let mut s = Stream::from(text);
+    let mut selectors = Vec::new();
+
+    loop {
+        let (selector, offset) = parse(s.slice_tail(), ParseOptions::default());
+        s.advance(offset);
+        s.skip_spaces();
+
+        match selector {
+            Ok(selector) => selectors.push(selector),
+            Err(_) => return (None, s.pos()),
+        }
+
+        if s.curr_byte() == Ok(b',') {
+            s.advance(1);
+        } else {
+            break;
+        }
+    }
+
+    (Some(SelectorList { selectors }), s.pos())
+}
+
+/// Selectors are separated by `, `.
+///
+/// Use the alternate flag (`{:#}`) to separate them by `,` instead.
+/// Selectors will be compacted as well.
+impl fmt::Display for SelectorList<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let compact = f.alternate();
+
+        for (i, selector) in self.selectors.iter().enumerate() {
+            if i != 0 {
+                write!(f, "{}", if compact { "," } else { ", " })?;
+            }
+
+            if compact {
+                write!(f, "{:#}", selector)?;
+            } else {
+                write!(f, "{}", selector)?;
             }
         }
 
@@ -403,7 +1300,7 @@ impl<'a> fmt::Display for Selector<'a> {
 
 
 /// A selector token.
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
 pub enum SelectorToken<'a> {
     /// `*`
     UniversalSelector,
@@ -411,21 +1308,60 @@ pub enum SelectorToken<'a> {
     /// `div`
     TypeSelector(&'a str),
 
+    /// `svg|`
+    ///
+    /// A namespace prefix of the following type or universal selector.
+    /// Contains `*` for any namespace and an empty string for no namespace, like in `|rect`.
+    NamespacePrefix(&'a str),
+
     /// `.class`
-    ClassSelector(&'a str),
+    ///
+    /// Escapes are decoded, like `md:flex` for `.md\:flex`.
+    /// Allocates only when the class has escapes.
+    ClassSelector(Cow<'a, str>),
 
     /// `#id`
-    IdSelector(&'a str),
+    ///
+    /// Escapes are decoded, like for `ClassSelector`.
+    IdSelector(Cow<'a, str>),
 
     /// `[color=red]`
-    AttributeSelector(&'a str, AttributeOperator<'a>),
+    ///
+    /// The third field indicates that the value was quoted, like in `[color='red']`.
+    /// Quoted and unquoted values are otherwise identical.
+    ///
+    /// The last field indicates the `i` flag, like in `[color=red i]`,
+    /// i.e. the value must be compared ASCII case-insensitively.
+    /// The `s` flag is the default one.
+    AttributeSelector(&'a str, AttributeOperator<'a>, bool, bool),
 
     /// `:first-child`
     PseudoClass(&'a str),
 
+    /// `::before`
+    PseudoElement(&'a str),
+
     /// `:lang(en)`
     LangPseudoClass(&'a str),
 
+    /// `:nth-child(2n+1)`
+    NthChildPseudoClass(i32, i32),
+
+    /// `:not(.class)`
+    ///
+    /// Contains an unparsed argument.
+    NotPseudoClass(&'a str),
+
+    /// `:is(.a, .b)`
+    ///
+    /// Contains unparsed arguments.
+    IsPseudoClass(&'a str),
+
+    /// `:where(.a, .b)`
+    ///
+    /// Contains unparsed arguments.
+    WherePseudoClass(&'a str),
+
     /// `a b`
     DescendantCombinator,
 
@@ -434,11 +1370,22 @@ pub enum SelectorToken<'a> {
 
     /// `a + b`
     AdjacentCombinator,
+
+    /// `a || b`
+    ColumnCombinator,
+
+    /// `/* comment */`
+    ///
+    /// Contains a comment text without delimiters.
+    /// Produced only by `SelectorTokenizer::new_with_comments`.
+    Comment(&'a str),
 }
 
 
 /// A selector tokenizer.
 ///
+/// Tokenizer will stop after the first error.
+///
 /// # Example
 ///
 /// ```
@@ -451,10 +1398,15 @@ pub enum SelectorToken<'a> {
 /// assert_eq!(t.next().unwrap().unwrap(), SelectorToken::PseudoClass("first-child"));
 /// assert!(t.next().is_none());
 /// ```
+#[derive(Clone)]
 pub struct SelectorTokenizer<'a> {
-    stream: Stream<'a>,
+    stream: Stream<'a, 'static>,
     after_combinator: bool,
     finished: bool,
+    comments: bool,
+    // Selectors keep escaped idents to write them back as is.
+    unescape: bool,
+    token_start: usize,
 }
 
 impl<'a> From<&'a str> for SelectorTokenizer<'a> {
@@ -463,7 +1415,158 @@ impl<'a> From<&'a str> for SelectorTokenizer<'a> {
             stream: Stream::from(text),
             after_combinator: true,
             finished: false,
+            comments: false,
+            unescape: true,
+            token_start: 0,
+        }
+    }
+}
+
+impl<'a> SelectorTokenizer<'a> {
+    /// Creates a new tokenizer that will produce `SelectorToken::Comment`
+    /// instead of skipping comments.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use simplecss::{SelectorTokenizer, SelectorToken};
+    ///
+    /// let mut t = SelectorTokenizer::new_with_comments("div /* text */ p");
+    /// assert_eq!(t.next().unwrap().unwrap(), SelectorToken::TypeSelector("div"));
+    /// assert_eq!(t.next().unwrap().unwrap(), SelectorToken::DescendantCombinator);
+    /// assert_eq!(t.next().unwrap().unwrap(), SelectorToken::Comment(" text "));
+    /// assert_eq!(t.next().unwrap().unwrap(), SelectorToken::TypeSelector("p"));
+    /// assert!(t.next().is_none());
+    /// ```
+    pub fn new_with_comments(text: &'a str) -> Self {
+        SelectorTokenizer {
+            comments: true,
+            ..SelectorTokenizer::from(text)
+        }
+    }
+
+    /// Returns the next token without advancing the tokenizer.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use simplecss::{SelectorTokenizer, SelectorToken};
+    ///
+    /// let mut t = SelectorTokenizer::from("div p");
+    /// assert_eq!(t.peek().unwrap().unwrap(), SelectorToken::TypeSelector("div"));
+    /// assert_eq!(t.next().unwrap().unwrap(), SelectorToken::TypeSelector("div"));
+    /// ```
+    pub fn peek(&self) -> Option<Result<SelectorToken<'a>, Error>> {
+        self.clone().next()
+    }
+
+    /// Returns the current byte offset in the text.
+    ///
+    /// The tokenizer is cheap to clone, so a clone can be used to save its state
+    /// and restore it later, e.g. to backtrack.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use simplecss::{SelectorTokenizer, SelectorToken};
+    ///
+    /// let mut t = SelectorTokenizer::from("div > p");
+    /// t.next();
+    /// let saved = t.clone();
+    /// assert_eq!(t.next().unwrap().unwrap(), SelectorToken::ChildCombinator);
+    /// assert_eq!(t.pos(), 5);
+    ///
+    /// t = saved;
+    /// assert_eq!(t.pos(), 3);
+    /// assert_eq!(t.next().unwrap().unwrap(), SelectorToken::ChildCombinator);
+    /// ```
+    pub fn pos(&self) -> usize {
+        self.stream.pos()
+    }
+
+    /// Returns the next token with its byte range in the text.
+    ///
+    /// Skipped whitespaces and comments are not a part of the range,
+    /// except for `SelectorToken::DescendantCombinator`, which covers them.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use simplecss::{SelectorTokenizer, SelectorToken};
+    ///
+    /// let mut t = SelectorTokenizer::from("div  > #id");
+    /// assert_eq!(t.next_spanned().unwrap().unwrap(), (SelectorToken::TypeSelector("div"), 0..3));
+    /// assert_eq!(t.next_spanned().unwrap().unwrap(), (SelectorToken::ChildCombinator, 5..6));
+    /// assert_eq!(t.next_spanned().unwrap().unwrap(), (SelectorToken::IdSelector("id".into()), 7..10));
+    /// assert!(t.next_spanned().is_none());
+    /// ```
+    pub fn next_spanned(&mut self) -> Option<Result<(SelectorToken<'a>, Range<usize>), Error>> {
+        let token = self.next()?;
+        Some(token.map(|token| (token, self.token_start..self.stream.pos())))
+    }
+
+    fn decode(&self, ident: &'a str) -> Cow<'a, str> {
+        if self.unescape {
+            unescape(ident)
+        } else {
+            Cow::Borrowed(ident)
+        }
+    }
+
+    fn consume_attribute_value(&mut self) -> Result<(&'a str, bool), Error> {
+        self.stream.skip_spaces();
+        match self.stream.curr_byte()? {
+            b'\'' | b'"' => Ok((self.stream.consume_string()?, true)),
+            _ => {
+                // An unquoted value must be a valid ident.
+                let value = self.stream.consume_ident()
+                    .map_err(|_| Error::InvalidAttributeSelector)?;
+                Ok((value, false))
+            }
+        }
+    }
+
+    // A namespace prefix must be followed by a type or universal selector.
+    fn check_namespaced_selector(&self) -> Result<(), Error> {
+        if self.stream.curr_byte() == Ok(b'*') {
+            return Ok(());
+        }
+
+        let mut s = self.stream;
+        s.consume_ident().map(|_| ())
+    }
+
+    fn consume_pseudo_class_args(&mut self) -> Result<&'a str, Error> {
+        self.stream.consume_byte(b'(')?;
+        let start = self.stream.pos();
+
+        // Skip until the matching `)`, but not the one inside a string.
+        let mut depth = 0;
+        while let Ok(c) = self.stream.curr_byte() {
+            match c {
+                b'(' => depth += 1,
+                b')' if depth == 0 => break,
+                b')' => depth -= 1,
+                b'\'' | b'"' => {
+                    self.stream.consume_string()?;
+                    continue;
+                }
+                _ => {}
+            }
+
+            self.stream.advance(1);
         }
+
+        let inner = self.stream.slice_back(start);
+        self.stream.consume_byte(b')')?;
+        Ok(inner)
+    }
+
+    fn consume_comment(&mut self) -> Result<&'a str, Error> {
+        let start = self.stream.pos();
+        self.stream.skip_comment()?;
+        let comment = self.stream.slice_back(start);
+        Ok(&comment[2..comment.len() - 2])
     }
 }
 
@@ -480,6 +1583,8 @@ impl<'a> Iterator for SelectorTokenizer<'a> {
             return None;
         }
 
+        self.token_start = self.stream.pos();
+
         macro_rules! try2 {
             ($e:expr) => {
                 match $e {
@@ -499,46 +1604,79 @@ impl<'a> Iterator for SelectorTokenizer<'a> {
                     return Some(Err(Error::UnexpectedSelector));
                 }
 
+                if self.stream.slice_tail().starts_with("*|") && !self.stream.slice_tail().starts_with("*||") {
+                    self.stream.advance(2);
+                    try2!(self.check_namespaced_selector());
+                    return Some(Ok(SelectorToken::NamespacePrefix("*")));
+                }
+
                 self.after_combinator = false;
                 self.stream.advance(1);
                 Some(Ok(SelectorToken::UniversalSelector))
             }
+            b'|' if self.stream.next_byte() == Ok(b'|') => {
+                if self.after_combinator {
+                    self.after_combinator = false;
+                    self.finished = true;
+                    return Some(Err(Error::UnexpectedCombinator));
+                }
+
+                self.stream.advance(2);
+                self.after_combinator = true;
+                Some(Ok(SelectorToken::ColumnCombinator))
+            }
+            b'|' => {
+                if !self.after_combinator {
+                    self.finished = true;
+                    return Some(Err(Error::UnexpectedSelector));
+                }
+
+                self.stream.advance(1);
+                try2!(self.check_namespaced_selector());
+                Some(Ok(SelectorToken::NamespacePrefix("")))
+            }
             b'#' => {
                 self.after_combinator = false;
                 self.stream.advance(1);
                 let ident = try2!(self.stream.consume_ident());
-                Some(Ok(SelectorToken::IdSelector(ident)))
+                Some(Ok(SelectorToken::IdSelector(self.decode(ident))))
             }
             b'.' => {
                 self.after_combinator = false;
                 self.stream.advance(1);
                 let ident = try2!(self.stream.consume_ident());
-                Some(Ok(SelectorToken::ClassSelector(ident)))
+                Some(Ok(SelectorToken::ClassSelector(self.decode(ident))))
             }
             b'[' => {
                 self.after_combinator = false;
                 self.stream.advance(1);
+                self.stream.skip_spaces();
                 let ident = try2!(self.stream.consume_ident());
+                self.stream.skip_spaces();
 
+                let mut quoted = false;
                 let op = match try2!(self.stream.curr_byte()) {
                     b']' => {
                         AttributeOperator::Exists
                     }
                     b'=' => {
                         self.stream.advance(1);
-                        let value = try2!(self.stream.consume_string());
+                        let (value, is_quoted) = try2!(self.consume_attribute_value());
+                        quoted = is_quoted;
                         AttributeOperator::Matches(value)
                     }
                     b'~' => {
                         self.stream.advance(1);
                         try2!(self.stream.consume_byte(b'='));
-                        let value = try2!(self.stream.consume_string());
+                        let (value, is_quoted) = try2!(self.consume_attribute_value());
+                        quoted = is_quoted;
                         AttributeOperator::Contains(value)
                     }
                     b'|' => {
                         self.stream.advance(1);
                         try2!(self.stream.consume_byte(b'='));
-                        let value = try2!(self.stream.consume_string());
+                        let (value, is_quoted) = try2!(self.consume_attribute_value());
+                        quoted = is_quoted;
                         AttributeOperator::StartsWith(value)
                     }
                     _ => {
@@ -547,13 +1685,42 @@ impl<'a> Iterator for SelectorTokenizer<'a> {
                     }
                 };
 
-                try2!(self.stream.consume_byte(b']'));
+                self.stream.skip_spaces();
 
-                Some(Ok(SelectorToken::AttributeSelector(ident, op)))
+                // An optional `i` or `s` flag, like `[type=submit i]`.
+                let mut ignore_case = false;
+                if op != AttributeOperator::Exists && self.stream.curr_byte() != Ok(b']') {
+                    let flag = try2!(self.stream.consume_ident()
+                        .map_err(|_| Error::InvalidAttributeSelector));
+                    if flag.eq_ignore_ascii_case("i") {
+                        ignore_case = true;
+                    } else if !flag.eq_ignore_ascii_case("s") {
+                        // Like `[type=check box]`.
+                        self.finished = true;
+                        return Some(Err(Error::InvalidAttributeSelector));
+                    }
+
+                    self.stream.skip_spaces();
+                }
+
+                if self.stream.curr_byte() != Ok(b']') {
+                    self.finished = true;
+                    return Some(Err(Error::InvalidAttributeSelector));
+                }
+                self.stream.advance(1);
+
+                Some(Ok(SelectorToken::AttributeSelector(ident, op, quoted, ignore_case)))
             }
             b':' => {
                 self.after_combinator = false;
                 self.stream.advance(1);
+
+                if self.stream.curr_byte() == Ok(b':') {
+                    self.stream.advance(1);
+                    let ident = try2!(self.stream.consume_ident());
+                    return Some(Ok(SelectorToken::PseudoElement(ident)));
+                }
+
                 let ident = try2!(self.stream.consume_ident());
 
                 if ident == "lang" {
@@ -561,12 +1728,31 @@ impl<'a> Iterator for SelectorTokenizer<'a> {
                     let lang = self.stream.consume_bytes(|c| c != b')').trim();
                     try2!(self.stream.consume_byte(b')'));
 
-                    if lang.is_empty() {
+                    // A language range, like `en` or `zh-Hant`.
+                    let is_range = lang.bytes().all(|c| c.is_ascii_alphanumeric() || c == b'-')
+                        && !lang.starts_with('-') && !lang.ends_with('-');
+                    if lang.is_empty() || !is_range {
                         self.finished = true;
                         return Some(Err(Error::InvalidLanguagePseudoClass));
                     }
 
                     Some(Ok(SelectorToken::LangPseudoClass(lang)))
+                } else if ident == "nth-child" {
+                    try2!(self.stream.consume_byte(b'('));
+                    let expr = self.stream.consume_bytes(|c| c != b')');
+                    try2!(self.stream.consume_byte(b')'));
+
+                    let (a, b) = try2!(parse_nth_child(expr));
+                    Some(Ok(SelectorToken::NthChildPseudoClass(a, b)))
+                } else if ident == "not" {
+                    let inner = try2!(self.consume_pseudo_class_args());
+                    Some(Ok(SelectorToken::NotPseudoClass(inner)))
+                } else if ident == "is" {
+                    let inner = try2!(self.consume_pseudo_class_args());
+                    Some(Ok(SelectorToken::IsPseudoClass(inner)))
+                } else if ident == "where" {
+                    let inner = try2!(self.consume_pseudo_class_args());
+                    Some(Ok(SelectorToken::WherePseudoClass(inner)))
                 } else {
                     Some(Ok(SelectorToken::PseudoClass(ident)))
                 }
@@ -593,6 +1779,13 @@ impl<'a> Iterator for SelectorTokenizer<'a> {
                 self.after_combinator = true;
                 Some(Ok(SelectorToken::AdjacentCombinator))
             }
+            b'~' if self.after_combinator => {
+                // The general sibling combinator is not supported,
+                // but it's still a combinator and not an invalid ident.
+                self.after_combinator = false;
+                self.finished = true;
+                Some(Err(Error::UnexpectedCombinator))
+            }
             b' ' | b'\t' | b'\n' | b'\r' | b'\x0C' => {
                 self.stream.skip_spaces();
 
@@ -600,12 +1793,22 @@ impl<'a> Iterator for SelectorTokenizer<'a> {
                     return self.next();
                 }
 
-                while self.stream.curr_byte() == Ok(b'/') {
-                    try2!(self.stream.skip_comment());
-                    self.stream.skip_spaces();
+                // Comments will be returned later, so look ahead using a copy.
+                let mut s = self.stream;
+                while s.curr_byte() == Ok(b'/') {
+                    try2!(s.skip_comment());
+                    s.skip_spaces();
+                }
+
+                if !self.comments {
+                    self.stream = s;
                 }
 
-                match self.stream.curr_byte() {
+                if s.slice_tail().starts_with("||") {
+                    return self.next();
+                }
+
+                match s.curr_byte() {
                     Ok(b'>') | Ok(b'+') | Ok(b',') | Ok(b'{') | Err(_) => {
                         self.next()
                     }
@@ -623,7 +1826,10 @@ impl<'a> Iterator for SelectorTokenizer<'a> {
             }
             b'/' => {
                 if self.stream.next_byte() == Ok(b'*') {
-                    try2!(self.stream.skip_comment());
+                    let comment = try2!(self.consume_comment());
+                    if self.comments {
+                        return Some(Ok(SelectorToken::Comment(comment)));
+                    }
                 } else {
                     self.finished = true;
                 }
@@ -642,9 +1848,17 @@ impl<'a> Iterator for SelectorTokenizer<'a> {
                     return Some(Err(Error::UnexpectedSelector));
                 }
 
+                if self.stream.curr_byte() == Ok(b'|') && self.stream.next_byte() != Ok(b'|') {
+                    self.stream.advance(1);
+                    try2!(self.check_namespaced_selector());
+                    return Some(Ok(SelectorToken::NamespacePrefix(ident)));
+                }
+
                 self.after_combinator = false;
                 Some(Ok(SelectorToken::TypeSelector(ident)))
             }
         }
     }
 }
+
+impl<'a> FusedIterator for SelectorTokenizer<'a> {}
@@ -0,0 +1,77 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::stream::Stream;
+use crate::{consume_term, Declaration};
+
+// Shorthands that use the 1-to-4 values expansion and their longhands name format.
+const BOX_SHORTHANDS: &[(&str, &str, &str)] = &[
+    ("margin", "margin-", ""),
+    ("padding", "padding-", ""),
+    ("border-width", "border-", "-width"),
+    ("border-style", "border-", "-style"),
+    ("border-color", "border-", "-color"),
+];
+
+/// Expands a shorthand declaration into longhand ones.
+///
+/// Supports `margin`, `padding`, `border-width`, `border-style` and `border-color`,
+/// which are expanded into top, right, bottom and left longhands
+/// using the 1-to-4 values rule.
+///
+/// Returns `None` for other properties, an invalid number of values
+/// or values with `var()`, which can be expanded only after variables resolving.
+/// The `!important` flag is not preserved.
+///
+/// # Example
+///
+/// ```
+/// use simplecss::{expand_shorthand, Declaration};
+///
+/// let declaration = Declaration::new("margin", "1px 2px", false);
+/// assert_eq!(expand_shorthand(&declaration), Some(vec![
+///     ("margin-top".to_string(), "1px".to_string()),
+///     ("margin-right".to_string(), "2px".to_string()),
+///     ("margin-bottom".to_string(), "1px".to_string()),
+///     ("margin-left".to_string(), "2px".to_string()),
+/// ]));
+///
+/// assert_eq!(expand_shorthand(&Declaration::new("color", "red", false)), None);
+/// ```
+pub fn expand_shorthand(declaration: &Declaration) -> Option<Vec<(String, String)>> {
+    let (_, prefix, suffix) = BOX_SHORTHANDS.iter()
+        .find(|(name, _, _)| declaration.name.eq_ignore_ascii_case(name))?;
+
+    let mut values = Vec::new();
+    let mut s = Stream::from(declaration.value);
+    loop {
+        s.skip_spaces_and_comments().ok()?;
+        if s.at_end() {
+            break;
+        }
+
+        let start = s.pos();
+        consume_term(&mut s).ok()?;
+        match s.slice_back(start) {
+            "," => return None,
+            term => values.push(term),
+        }
+    }
+
+    if values.iter().any(|v| v.as_bytes().windows(4).any(|w| w.eq_ignore_ascii_case(b"var("))) {
+        return None;
+    }
+
+    let (top, right, bottom, left) = match values.as_slice() {
+        [all] => (all, all, all, all),
+        [vertical, horizontal] => (vertical, horizontal, vertical, horizontal),
+        [top, horizontal, bottom] => (top, horizontal, bottom, horizontal),
+        [top, right, bottom, left] => (top, right, bottom, left),
+        _ => return None,
+    };
+
+    Some([("top", top), ("right", right), ("bottom", bottom), ("left", left)].iter()
+        .map(|(side, value)| (format!("{}{}{}", prefix, side, suffix), value.to_string()))
+        .collect())
+}
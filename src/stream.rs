@@ -1,4 +1,6 @@
-use std::str;
+use core::str;
+
+use alloc::vec::Vec;
 
 use crate::{Error, TextPos};
 
@@ -7,7 +9,6 @@ trait CssCharExt {
     fn is_name_start(&self) -> bool;
     fn is_name_char(&self) -> bool;
     fn is_non_ascii(&self) -> bool;
-    fn is_escape(&self) -> bool;
 }
 
 impl CssCharExt for char {
@@ -15,7 +16,7 @@ impl CssCharExt for char {
     fn is_name_start(&self) -> bool {
         match *self {
             '_' | 'a'..='z' | 'A'..='Z' => true,
-            _ => self.is_non_ascii() || self.is_escape(),
+            _ => self.is_non_ascii(),
         }
     }
 
@@ -23,7 +24,7 @@ impl CssCharExt for char {
     fn is_name_char(&self) -> bool {
         match *self {
             '_' | 'a'..='z' | 'A'..='Z' | '0'..='9' | '-' => true,
-            _ => self.is_non_ascii() || self.is_escape(),
+            _ => self.is_non_ascii(),
         }
     }
 
@@ -31,37 +32,105 @@ impl CssCharExt for char {
     fn is_non_ascii(&self) -> bool {
         *self as u32 > 237
     }
+}
 
-    #[inline]
-    fn is_escape(&self) -> bool {
-        // TODO: this
-        false
+
+/// Newline offsets of a text.
+///
+/// Allows converting a byte offset into a row/column pair in O(log n) instead of rescanning the text.
+#[derive(Default, Debug)]
+pub(crate) struct LineIndex {
+    newlines: Vec<usize>,
+}
+
+impl LineIndex {
+    pub fn new(text: &str) -> Self {
+        LineIndex {
+            newlines: text.bytes().enumerate().filter(|(_, c)| *c == b'\n').map(|(i, _)| i).collect(),
+        }
+    }
+
+    fn text_pos(&self, text: &str, pos: usize) -> TextPos {
+        let newlines = &self.newlines;
+        let row = newlines.partition_point(|n| *n < pos);
+        let line_start = if row == 0 { 0 } else { newlines[row - 1] + 1 };
+        let col = text[line_start..pos].chars().count() + 1;
+        TextPos::new(row as u32 + 1, col as u32, pos)
     }
 }
 
 
-#[derive(Clone, Copy, PartialEq, Debug)]
-pub(crate) struct Stream<'a> {
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Stream<'a, 'l> {
     text: &'a str,
     pos: usize,
     end: usize,
+    lines: Option<&'l LineIndex>,
 }
 
-impl<'a> From<&'a str> for Stream<'a> {
+impl<'a> From<&'a str> for Stream<'a, '_> {
     fn from(text: &'a str) -> Self {
         Stream::new(text)
     }
 }
 
-impl<'a> Stream<'a> {
+impl<'a, 'l> Stream<'a, 'l> {
     pub fn new(text: &'a str) -> Self {
         Stream {
             text,
             pos: 0,
             end: text.len(),
+            lines: None,
+        }
+    }
+
+    pub fn new_bound(text: &'a str, start: usize, end: usize) -> Self {
+        assert!(start <= end && end <= text.len(), "invalid stream bounds");
+        assert!(text.is_char_boundary(start) && text.is_char_boundary(end),
+                "stream bounds must be on a char boundary");
+
+        Stream {
+            text,
+            pos: start,
+            end,
+            lines: None,
         }
     }
 
+    /// Makes the stream use a shared `LineIndex` for text positions calculation.
+    ///
+    /// The index must be used only with the same text.
+    pub fn with_line_index<'n>(self, lines: &'n LineIndex) -> Stream<'a, 'n> {
+        Stream {
+            text: self.text,
+            pos: self.pos,
+            end: self.end,
+            lines: Some(lines),
+        }
+    }
+
+    /// Limits the rest of the stream to `len` bytes, rounded down to a char boundary.
+    ///
+    /// Returns `true` when the stream was shortened.
+    pub fn limit(&mut self, len: usize) -> bool {
+        let mut end = self.pos.saturating_add(len);
+        if end >= self.end {
+            return false;
+        }
+
+        while !self.text.is_char_boundary(end) {
+            end -= 1;
+        }
+
+        self.end = end;
+        true
+    }
+
+    #[inline]
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+
     #[inline]
     pub fn pos(&self) -> usize {
         self.pos
@@ -158,7 +227,7 @@ impl<'a> Stream<'a> {
 
     #[inline]
     pub fn slice_tail(&self) -> &'a str {
-        &self.text[self.pos..]
+        &self.text[self.pos..self.end]
     }
 
     #[inline]
@@ -185,26 +254,21 @@ impl<'a> Stream<'a> {
     pub fn consume_ident(&mut self) -> Result<&'a str, Error> {
         let start = self.pos();
 
-        if self.curr_byte() == Ok(b'-') {
-            self.advance(1);
-        }
+        if self.slice_tail().starts_with("--") {
+            // A custom property name, like `--main-color`.
+            // Can be followed by any name chars.
+            self.advance(2);
+        } else {
+            if self.curr_byte() == Ok(b'-') {
+                self.advance(1);
+            }
 
-        let mut iter = self.chars();
-        if let Some(c) = iter.next() {
-            if c.is_name_start() {
-                self.advance(c.len_utf8());
-            } else {
+            if !self.at_end() && !self.consume_name_char(true) {
                 return Err(Error::InvalidIdent(self.gen_text_pos_from(start)));
             }
         }
 
-        for c in iter {
-            if c.is_name_char() {
-                self.advance(c.len_utf8());
-            } else {
-                break;
-            }
-        }
+        while self.consume_name_char(false) {}
 
         if start == self.pos() {
             return Err(Error::InvalidIdent(self.gen_text_pos_from(start)));
@@ -214,11 +278,74 @@ impl<'a> Stream<'a> {
         Ok(name)
     }
 
+    pub fn consume_name(&mut self) -> Result<&'a str, Error> {
+        // Unlike an ident, a name can start with any name character, like a digit.
+        let start = self.pos();
+
+        while self.consume_name_char(false) {}
+
+        if start == self.pos() {
+            return Err(Error::InvalidIdent(self.gen_text_pos_from(start)));
+        }
+
+        Ok(self.slice_back(start))
+    }
+
+    fn consume_name_char(&mut self, is_start: bool) -> bool {
+        if self.try_consume_escape() {
+            return true;
+        }
+
+        match self.chars().next() {
+            Some(c) if (is_start && c.is_name_start()) || (!is_start && c.is_name_char()) => {
+                self.advance(c.len_utf8());
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn try_consume_escape(&mut self) -> bool {
+        // An escape is a `\` followed by 1-6 hex digits and an optional whitespace,
+        // or by any other char except a newline.
+
+        if self.curr_byte() != Ok(b'\\') {
+            return false;
+        }
+
+        let c = match self.text[self.pos + 1..self.end].chars().next() {
+            Some(c) if c != '\n' && c != '\r' && c != '\x0C' => c,
+            _ => return false,
+        };
+
+        self.advance(1);
+
+        if c.is_ascii_hexdigit() {
+            let mut len = 0;
+            while len < 6 && self.curr_byte().map(|c| c.is_ascii_hexdigit()) == Ok(true) {
+                self.advance(1);
+                len += 1;
+            }
+
+            if self.slice_tail().starts_with("\r\n") {
+                self.advance(2);
+            } else {
+                match self.curr_byte() {
+                    Ok(b' ') | Ok(b'\t') | Ok(b'\n') | Ok(b'\r') | Ok(b'\x0C') => self.advance(1),
+                    _ => {}
+                }
+            }
+        } else {
+            self.advance(c.len_utf8());
+        }
+
+        true
+    }
+
     pub fn consume_string(&mut self) -> Result<&'a str, Error> {
         // Check for opening quote.
         let quote = self.curr_byte()?;
         if quote == b'\'' || quote == b'"' {
-            let mut prev = quote;
             self.advance(1);
 
             let start = self.pos();
@@ -228,13 +355,19 @@ impl<'a> Stream<'a> {
 
                 // Advance until the closing quote.
                 if curr == quote {
-                    // Check for escaped quote.
-                    if prev != b'\\' {
-                        break;
+                    break;
+                }
+
+                // Skip escaped characters, including quotes, backslashes and newlines.
+                if curr == b'\\' {
+                    self.advance(1);
+                    if let Some(c) = self.chars().next() {
+                        self.advance(c.len_utf8());
                     }
+
+                    continue;
                 }
 
-                prev = curr;
                 self.advance(1);
             }
 
@@ -276,15 +409,19 @@ impl<'a> Stream<'a> {
 
     #[inline(never)]
     pub fn gen_text_pos(&self) -> TextPos {
+        if let Some(lines) = self.lines {
+            return lines.text_pos(self.text, self.pos);
+        }
+
         let row = Self::calc_curr_row(self.text, self.pos);
         let col = Self::calc_curr_col(self.text, self.pos);
-        TextPos::new(row, col)
+        TextPos::new(row, col, self.pos)
     }
 
     #[inline(never)]
     pub fn gen_text_pos_from(&self, pos: usize) -> TextPos {
         let mut s = *self;
-        s.pos = std::cmp::min(pos, self.text.len());
+        s.pos = core::cmp::min(pos, self.text.len());
         s.gen_text_pos()
     }
 
@@ -0,0 +1,150 @@
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::stream::Stream;
+use crate::{consume_function_args, Declaration};
+
+/// Resolves `var()` functions in declarations values.
+///
+/// `vars` contains custom properties values by their names, including the `--` prefix.
+/// For example, the ones declared in a `:root` rule.
+/// Variables values can reference other variables.
+///
+/// When a variable is not set, a fallback value will be used instead,
+/// like `red` in `var(--color, red)`. Fallbacks can contain `var()` as well.
+///
+/// Declarations with variables that cannot be resolved, i.e. missing variables
+/// without a fallback or cyclic ones, are skipped. The `!important` flag is not preserved.
+///
+/// To prevent a denial of service on malicious input, variables cannot reference each other
+/// deeper than 64 levels and a resolved value cannot be longer than 1 MiB.
+/// Declarations that exceed these limits are skipped too.
+///
+/// Returns a list of declarations names and resolved values.
+///
+/// # Example
+///
+/// ```
+/// use std::collections::BTreeMap;
+/// use simplecss::{parse_inline_declarations, resolve_variables};
+///
+/// let mut vars = BTreeMap::new();
+/// vars.insert("--main", "red");
+///
+/// let declarations = parse_inline_declarations("fill: var(--main); stroke: var(--none, var(--main))");
+/// assert_eq!(resolve_variables(&declarations, &vars), vec![
+///     ("fill".to_string(), "red".to_string()),
+///     ("stroke".to_string(), "red".to_string()),
+/// ]);
+/// ```
+pub fn resolve_variables(declarations: &[Declaration], vars: &BTreeMap<&str, &str>) -> Vec<(String, String)> {
+    let mut resolver = Resolver {
+        vars,
+        cache: BTreeMap::new(),
+        stack: Vec::new(),
+        cyclic: BTreeSet::new(),
+    };
+
+    declarations.iter().filter_map(|d| {
+        resolver.resolve_value(d.value).map(|value| (d.name.to_string(), value))
+    }).collect()
+}
+
+// A maximum number of variables that are being resolved at once.
+const MAX_DEPTH: usize = 64;
+
+// A maximum length of a resolved value in bytes.
+const MAX_LENGTH: usize = 1024 * 1024;
+
+struct Resolver<'a, 'v> {
+    vars: &'v BTreeMap<&'a str, &'a str>,
+    // Resolved variables values, so each variable is resolved only once.
+    // `None` indicates an invalid variable.
+    cache: BTreeMap<&'a str, Option<String>>,
+    // Names of the variables that are being resolved,
+    // to detect cycles, like `--a: var(--b); --b: var(--a)`.
+    stack: Vec<&'a str>,
+    // Variables that reference themselves, directly or indirectly.
+    cyclic: BTreeSet<&'a str>,
+}
+
+impl<'a> Resolver<'a, '_> {
+    fn resolve_value(&mut self, text: &'a str) -> Option<String> {
+        let mut resolved = String::new();
+        let mut s = Stream::from(text);
+        let mut last = 0;
+        while !s.at_end() {
+            match s.curr_byte_unchecked() {
+                b'\'' | b'"' => {
+                    // Strings are copied as is.
+                    if s.consume_string().is_err() {
+                        break;
+                    }
+                }
+                _ if is_var_start(text, s.pos()) => {
+                    let start = s.pos();
+                    s.advance(3);
+                    consume_function_args(&mut s).ok()?;
+
+                    let args = s.slice_range(start + 4, s.pos() - 1);
+                    resolved.push_str(s.slice_range(last, start));
+                    resolved.push_str(&self.resolve_var(args)?);
+                    last = s.pos();
+
+                    if resolved.len() > MAX_LENGTH {
+                        return None;
+                    }
+                }
+                _ => s.advance(1),
+            }
+        }
+
+        s.jump_to_end();
+        resolved.push_str(s.slice_back(last));
+        if resolved.len() > MAX_LENGTH {
+            return None;
+        }
+
+        Some(resolved.trim().to_string())
+    }
+
+    fn resolve_var(&mut self, args: &'a str) -> Option<String> {
+        // A variable name cannot contain a comma, so the first one starts a fallback.
+        let (name, fallback) = match args.split_once(',') {
+            Some((name, fallback)) => (name.trim(), Some(fallback)),
+            None => (args.trim(), None),
+        };
+
+        // A cyclic or a too deep variable is invalid, so a fallback should be used instead.
+        let value = if let Some(value) = self.cache.get(name) {
+            value.clone()
+        } else if let Some(idx) = self.stack.iter().position(|n| *n == name) {
+            // All the variables in a cycle are invalid.
+            self.cyclic.extend(&self.stack[idx..]);
+            None
+        } else if self.stack.len() >= MAX_DEPTH {
+            None
+        } else if let Some(text) = self.vars.get(name) {
+            self.stack.push(name);
+            let value = self.resolve_value(text).filter(|_| !self.cyclic.contains(name));
+            self.stack.pop();
+            self.cache.insert(name, value.clone());
+            value
+        } else {
+            None
+        };
+
+        value.or_else(|| self.resolve_value(fallback?))
+    }
+}
+
+fn is_var_start(text: &str, pos: usize) -> bool {
+    // Must not be a part of another function name, like `myvar()`.
+    // Bytes are used, since `pos` can be inside a multibyte char.
+    let bytes = text.as_bytes();
+    let is_name_end = pos != 0
+        && (bytes[pos - 1].is_ascii_alphanumeric() || bytes[pos - 1] == b'-' || bytes[pos - 1] == b'_');
+
+    !is_name_end && bytes.get(pos..pos + 4).map(|v| v.eq_ignore_ascii_case(b"var(")) == Some(true)
+}
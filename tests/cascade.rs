@@ -0,0 +1,42 @@
+use simplecss::*;
+
+fn resolve<'a>(sheet: &'a StyleSheet<'a>) -> Vec<(&'a str, &'a str)> {
+    let declarations = cascade(sheet.rules.iter().flat_map(|rule| {
+        let spec = rule.selector.specificity();
+        rule.declarations.iter().map(move |d| (spec, d))
+    }));
+
+    declarations.iter().map(|(name, d)| (*name, d.value)).collect()
+}
+
+#[test]
+fn specificity() {
+    let sheet = StyleSheet::parse("p.a { color:red } p { color:green; fill:green } * { fill:red }");
+    assert_eq!(resolve(&sheet), vec![("color", "red"), ("fill", "green")]);
+}
+
+#[test]
+fn important() {
+    let sheet = StyleSheet::parse("#a { color:red } p { color:green !important } #b.c { color:blue }");
+    assert_eq!(resolve(&sheet), vec![("color", "green")]);
+
+    let sheet = StyleSheet::parse("#a { color:red !important } p { color:green !important }");
+    assert_eq!(resolve(&sheet), vec![("color", "red")]);
+}
+
+#[test]
+fn escaped_names() {
+    let sheet = StyleSheet::parse(r"p.a { color:red } p { \63 olor:green !important }");
+    assert_eq!(resolve(&sheet), vec![("color", "green")]);
+}
+
+#[test]
+fn source_order() {
+    let a = Declaration::new("color", "red", false);
+    let b = Declaration::new("color", "green", false);
+    let c = Declaration::new("color", "blue", false);
+    let spec = Specificity([0, 1, 0]);
+    let declarations = cascade(vec![(spec, &a), (spec, &b), (Specificity([0, 0, 1]), &c)]);
+    assert_eq!(declarations["color"].value, "green");
+    assert!(cascade(Vec::new()).is_empty());
+}
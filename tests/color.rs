@@ -0,0 +1,63 @@
+use simplecss::*;
+
+fn declare(value: &str) -> Declaration<'_> {
+    Declaration::new("color", value, false)
+}
+
+#[test]
+fn parse_01() {
+    let red = Some(Color::new_rgba(255, 0, 0, 255));
+    assert_eq!(Color::parse("red"), red);
+    assert_eq!(Color::parse("RED"), red);
+    assert_eq!(Color::parse("#f00"), red);
+    assert_eq!(Color::parse("#F00F"), red);
+    assert_eq!(Color::parse("#ff0000"), red);
+    assert_eq!(Color::parse("#ff0000ff"), red);
+    assert_eq!(Color::parse("rgb(255,0,0)"), red);
+    assert_eq!(Color::parse("rgb( 255 , 0 , 0 )"), red);
+    assert_eq!(Color::parse("rgb(255 0 0)"), red);
+    assert_eq!(Color::parse("rgb(100%, 0%, 0%)"), red);
+    assert_eq!(Color::parse("RGBA(255, 0, 0, 1)"), red);
+}
+
+#[test]
+fn parse_02() {
+    assert_eq!(Color::parse("rgba(0, 0, 255, 0.5)"), Some(Color::new_rgba(0, 0, 255, 128)));
+    assert_eq!(Color::parse("rgb(0 0 255 / 50%)"), Some(Color::new_rgba(0, 0, 255, 128)));
+    assert_eq!(Color::parse("rgb(300, -10, 0)"), Some(Color::new_rgba(255, 0, 0, 255)));
+    assert_eq!(Color::parse("transparent"), Some(Color::new_rgba(0, 0, 0, 0)));
+    assert_eq!(Color::parse("rebeccapurple"), Some(Color::new_rgba(102, 51, 153, 255)));
+}
+
+#[test]
+fn parse_03() {
+    assert_eq!(Color::parse(""), None);
+    assert_eq!(Color::parse("#"), None);
+    assert_eq!(Color::parse("#ff"), None);
+    assert_eq!(Color::parse("#gg0000"), None);
+    assert_eq!(Color::parse("rgb(1, 2)"), None);
+    assert_eq!(Color::parse("rgb(1, 2, 3, 4, 5)"), None);
+    assert_eq!(Color::parse("rgb(1, 2, 3"), None);
+    assert_eq!(Color::parse("rgb(a, b, c)"), None);
+    assert_eq!(Color::parse("url(#f00)"), None);
+    assert_eq!(Color::parse("5px"), None);
+    assert_eq!(Color::parse("redd"), None);
+}
+
+#[test]
+fn value_color_eq_01() {
+    let values = ["#ff0000", "#f00", "red", "rgb(255,0,0)"];
+    for a in &values {
+        for b in &values {
+            assert!(declare(a).value_color_eq(&declare(b)), "{} != {}", a, b);
+        }
+    }
+}
+
+#[test]
+fn value_color_eq_02() {
+    assert!(!declare("red").value_color_eq(&declare("#f01")));
+    assert!(!declare("red").value_color_eq(&declare("rgba(255,0,0,0.5)")));
+    assert!(!declare("red").value_color_eq(&declare("none")));
+    assert!(!declare("none").value_color_eq(&declare("none")));
+}
@@ -15,11 +15,11 @@ macro_rules! tokenize {
 }
 
 fn declare<'a>(name: &'a str, value: &'a str) -> Declaration<'a> {
-    Declaration { name, value, important: false }
+    Declaration::new(name, value, false)
 }
 
 fn declare_important<'a>(name: &'a str, value: &'a str) -> Declaration<'a> {
-    Declaration { name, value, important: true }
+    Declaration::new(name, value, true)
 }
 
 tokenize!(tokenize_01, "", );
@@ -144,6 +144,390 @@ tokenize!(tokenize_34, "*zoom:1;",
     declare("zoom", "1")
 );
 
+tokenize!(tokenize_35, "--main-color: #fff",
+    declare("--main-color", "#fff")
+);
+
+tokenize!(tokenize_36, "color: var(--main-color, blue)",
+    declare("color", "var(--main-color, blue)")
+);
+
+tokenize!(tokenize_37, "color: var(--main-color, var(--fallback, red)) !important",
+    declare_important("color", "var(--main-color, var(--fallback, red))")
+);
+
+tokenize!(tokenize_38, "--x:1px;--y: var(--x)",
+    declare("--x", "1px"),
+    declare("--y", "var(--x)")
+);
+
+tokenize!(tokenize_39, "background: url(\"a)b.png\")",
+    declare("background", "url(\"a)b.png\")")
+);
+
+tokenize!(important_01, "color: white ! important",
+    declare_important("color", "white")
+);
+
+tokenize!(important_02, "color: white!important;",
+    declare_important("color", "white")
+);
+
+tokenize!(important_03, "color: white \t!\n  important ; width: 1px",
+    declare_important("color", "white"),
+    declare("width", "1px")
+);
+
+tokenize!(important_04, "color: white !IMPORTANT",
+    declare_important("color", "white")
+);
+
+tokenize!(important_05, "color: white ! ImPortant; width: 1px",
+    declare_important("color", "white"),
+    declare("width", "1px")
+);
+
+tokenize!(important_06, "color: red\n!\nimportant\n;",
+    declare_important("color", "red")
+);
+
+tokenize!(important_07, "color:\tred\t!\timportant\t;\twidth:\t1px",
+    declare_important("color", "red"),
+    declare("width", "1px")
+);
+
+tokenize!(important_08, "color: red\r\n!\r\n  important\r\n;\r\nwidth: 1px\r\n",
+    declare_important("color", "red"),
+    declare("width", "1px")
+);
+
+tokenize!(important_09, "color: red /* a */\n! /* b */\nimportant /* c */\n; width: 1px",
+    declare_important("color", "red"),
+    declare("width", "1px")
+);
+
+tokenize!(important_10, "color: red\x0C!\x0Cimportant",
+    declare_important("color", "red")
+);
+
+#[test]
+fn important_11() {
+    // Not an `important`.
+    let d = Declaration::parse_single("color: red !importantly").unwrap_err();
+    assert_eq!(d, Error::InvalidValue(TextPos::new(1, 12, 11)));
+}
+
+tokenize!(important_12, "color: red ! /*c*/ IMPORTANT; fill: red!/**//**/iMpOrTaNt",
+    declare_important("color", "red"),
+    declare_important("fill", "red")
+);
+
+#[test]
+fn split_important_01() {
+    assert_eq!(split_important("white"), ("white", false));
+    assert_eq!(split_important("white "), ("white", false));
+    assert_eq!(split_important("white !important"), ("white", true));
+    assert_eq!(split_important("white!important"), ("white", true));
+    assert_eq!(split_important("white ! important"), ("white", true));
+    assert_eq!(split_important("white \t!\n important \n"), ("white", true));
+}
+
+#[test]
+fn split_important_02() {
+    assert_eq!(split_important("white !IMPORTANT"), ("white", true));
+    assert_eq!(split_important("white ! Important"), ("white", true));
+}
+
+#[test]
+fn split_important_03() {
+    assert_eq!(split_important("important"), ("important", false));
+    assert_eq!(split_important("white important"), ("white important", false));
+    assert_eq!(split_important("white !importantly"), ("white !importantly", false));
+    assert_eq!(split_important("!important"), ("", true));
+    assert_eq!(split_important("'ÿimportant'"), ("'ÿimportant'", false));
+}
+
+#[test]
+fn functions_01() {
+    let d = declare("transform", "translate(10,20) rotate(45) scale(2, 3)");
+    assert_eq!(d.functions(), vec![
+        ("translate", vec!["10", "20"]),
+        ("rotate", vec!["45"]),
+        ("scale", vec!["2", "3"]),
+    ]);
+}
+
+#[test]
+fn functions_02() {
+    let d = declare("transform", "matrix( 1.0 , 0, 0, -1, 10.5, 20 )");
+    assert_eq!(d.functions(), vec![("matrix", vec!["1.0", "0", "0", "-1", "10.5", "20"])]);
+}
+
+#[test]
+fn functions_03() {
+    let d = declare("background", "url(\"a,b).png\") no-repeat, linear-gradient(to right, rgb(0, 0, 0), red) 'f(x)'");
+    assert_eq!(d.functions(), vec![
+        ("url", vec!["\"a,b).png\""]),
+        ("linear-gradient", vec!["to right", "rgb(0, 0, 0)", "red"]),
+    ]);
+}
+
+#[test]
+fn functions_04() {
+    assert!(declare("color", "red").functions().is_empty());
+    assert_eq!(declare("transform", "rotate()").functions(), vec![("rotate", vec![])]);
+}
+
+tokenize!(math_functions_01, "width: calc(100% - 10px); height: calc(calc(100% - (2 * 5px)) + 1px) !important",
+    declare("width", "calc(100% - 10px)"),
+    declare_important("height", "calc(calc(100% - (2 * 5px)) + 1px)")
+);
+
+tokenize!(math_functions_02, "width: min(10px, 5vw) max( 1px , 2px ) clamp(1rem, calc( 1vw + 2px ), 3rem); color: red",
+    declare("width", "min(10px, 5vw) max( 1px , 2px ) clamp(1rem, calc( 1vw + 2px ), 3rem)"),
+    declare("color", "red")
+);
+
+#[test]
+fn math_functions_03() {
+    let terms: Vec<_> = value_tokens("calc(calc(1px + 2px) + 1px) min(1px,(2px)) 5px").collect();
+    assert_eq!(terms, vec!["calc(calc(1px + 2px) + 1px)", "min(1px,(2px))", "5px"]);
+
+    // Unbalanced parentheses.
+    assert!(Declaration::parse_single("width: calc((1px + 2px)").is_err());
+}
+
+#[test]
+fn raw_value_01() {
+    let mut t = DeclarationTokenizer::from("color:  red  /* c */; fill:blue");
+    let d = t.next().unwrap();
+    assert_eq!(d.value, "red");
+    assert_eq!(d.raw_value, "  red  /* c */");
+    assert_eq!(t.next().unwrap().raw_value, "blue");
+}
+
+#[test]
+fn raw_value_02() {
+    let d = declare("color", "red");
+    assert_eq!(d.raw_value, "red");
+    assert_eq!(d, DeclarationTokenizer::from("color : red ").next().unwrap());
+}
+
+#[test]
+fn custom_property_01() {
+    let d = Declaration::parse_single("--main-color: red").unwrap();
+    assert!(d.is_custom_property());
+    assert_eq!(d.variable_name(), Some("main-color"));
+
+    let d = Declaration::parse_single("-webkit-color: red").unwrap();
+    assert!(!d.is_custom_property());
+    assert_eq!(d.variable_name(), None);
+
+    let d = Declaration::new("--", "1", false);
+    assert!(d.is_custom_property());
+    assert_eq!(d.variable_name(), Some(""));
+}
+
+#[test]
+fn parse_single_01() {
+    assert_eq!(Declaration::parse_single("color:red").unwrap(), declare("color", "red"));
+    assert_eq!(Declaration::parse_single(" /* c */ color : red ; ; ").unwrap(), declare("color", "red"));
+    assert_eq!(Declaration::parse_single("color:red!IMPORTANT").unwrap(), declare_important("color", "red"));
+}
+
+#[test]
+fn parse_single_02() {
+    assert_eq!(Declaration::parse_single("color:red; fill:blue").unwrap_err(),
+               Error::UnexpectedData(TextPos::new(1, 12, 11)));
+    assert_eq!(Declaration::parse_single("color:red !importnt").unwrap_err(),
+               Error::InvalidValue(TextPos::new(1, 11, 10)));
+    assert_eq!(Declaration::parse_single("color:red }").unwrap_err(),
+               Error::UnexpectedData(TextPos::new(1, 11, 10)));
+    assert_eq!(Declaration::parse_single("color").unwrap_err(), Error::UnexpectedEndOfStream);
+    assert_eq!(Declaration::parse_single("color:").unwrap_err(), Error::InvalidValue(TextPos::new(1, 7, 6)));
+}
+
+#[test]
+fn inline_01() {
+    assert_eq!(parse_inline_declarations("fill: red; stroke:blue !important;; opacity:0.5"), vec![
+        declare("fill", "red"),
+        declare_important("stroke", "blue"),
+        declare("opacity", "0.5"),
+    ]);
+}
+
+#[test]
+fn inline_02() {
+    assert!(parse_inline_declarations("").is_empty());
+    assert!(parse_inline_declarations(" /* */ ").is_empty());
+    assert_eq!(parse_inline_declarations("fill:red; stroke; opacity:0.5"), vec![declare("fill", "red")]);
+}
+
+#[test]
+fn value_tokenizer_01() {
+    let tokens: Vec<_> = ValueTokenizer::from("url(img.png) url('img.png') url(\"a b.png\")").collect();
+    assert_eq!(tokens, vec![
+        ValueToken::Url("img.png".into(), false),
+        ValueToken::Url("img.png".into(), true),
+        ValueToken::Url("a b.png".into(), true),
+    ]);
+}
+
+#[test]
+fn value_tokenizer_02() {
+    let tokens: Vec<_> = ValueTokenizer::from("url(  spaced  ) URL( \"q\" ) url(a\\)\\26 b.png)").collect();
+    assert_eq!(tokens, vec![
+        ValueToken::Url("spaced".into(), false),
+        ValueToken::Url("q".into(), true),
+        ValueToken::Url("a)&b.png".into(), false),
+    ]);
+}
+
+#[test]
+fn value_tokenizer_03() {
+    let tokens: Vec<_> = ValueTokenizer::from("#fff url(data:image/png;base64,iVBO=) 5px, rgb(0, 0, 0) 'text'").collect();
+    assert_eq!(tokens, vec![
+        ValueToken::Term("#fff"),
+        ValueToken::Url("data:image/png;base64,iVBO=".into(), false),
+        ValueToken::Term("5px"),
+        ValueToken::Term(","),
+        ValueToken::Term("rgb(0, 0, 0)"),
+        ValueToken::Term("'text'"),
+    ]);
+}
+
+tokenize!(string_01, r#"content: "a\"b""#,
+    declare("content", r#""a\"b""#)
+);
+
+tokenize!(string_02, r"content: 'a\\'; color: red",
+    declare("content", r"'a\\'"),
+    declare("color", "red")
+);
+
+tokenize!(string_03, "content: \"a\\\nb\"; color: red",
+    declare("content", "\"a\\\nb\""),
+    declare("color", "red")
+);
+
+tokenize!(string_04, r#"font-family: "Font \"X\"", serif"#,
+    declare("font-family", r#""Font \"X\"", serif"#)
+);
+
+tokenize!(string_05, r#"content: "this is a string]}\"\"[{\\\"'"; color: red"#,
+    declare("content", r#""this is a string]}\"\"[{\\\"'""#),
+    declare("color", "red")
+);
+
+tokenize!(string_06, r#"content: "\""; color: red"#,
+    declare("content", r#""\"""#),
+    declare("color", "red")
+);
+
+#[test]
+fn value_tokenizer_04() {
+    // Unquoted URLs cannot contain whitespaces and quotes.
+    let tokens: Vec<_> = ValueTokenizer::from("red url(a b) blue").collect();
+    assert_eq!(tokens, vec![ValueToken::Term("red")]);
+    assert!(ValueTokenizer::from("url(a'b)").next().is_none());
+    assert!(ValueTokenizer::from("url('a'").next().is_none());
+}
+
+#[test]
+fn peek_01() {
+    let mut t = DeclarationTokenizer::from("color:red; fill:green");
+    assert_eq!(t.peek().unwrap(), declare("color", "red"));
+    assert_eq!(t.next().unwrap(), declare("color", "red"));
+    assert_eq!(t.peek().unwrap(), declare("fill", "green"));
+    assert_eq!(t.next().unwrap(), declare("fill", "green"));
+    assert!(t.peek().is_none());
+    assert!(t.next().is_none());
+}
+
+#[test]
+fn peek_02() {
+    let mut t = ValueTokenizer::from("url(a.png) red");
+    assert_eq!(t.peek().unwrap(), ValueToken::Url("a.png".into(), false));
+    assert_eq!(t.next().unwrap(), ValueToken::Url("a.png".into(), false));
+    assert_eq!(t.peek().unwrap(), ValueToken::Term("red"));
+    assert_eq!(t.next().unwrap(), ValueToken::Term("red"));
+    assert!(t.peek().is_none());
+}
+
+#[test]
+fn value_tokens_01() {
+    let tokens: Vec<_> = value_tokens("1px solid red").collect();
+    assert_eq!(tokens, vec!["1px", "solid", "red"]);
+}
+
+#[test]
+fn value_tokens_02() {
+    let tokens: Vec<_> = value_tokens("url(x) no-repeat, rgb(1, 2, 3) /* c */ -0.5em 50% \"a b\"").collect();
+    assert_eq!(tokens, vec!["url(x)", "no-repeat", ",", "rgb(1, 2, 3)", "-0.5em", "50%", "\"a b\""]);
+}
+
+#[test]
+fn value_tokens_03() {
+    assert_eq!(value_tokens("").next(), None);
+    assert_eq!(value_tokens("red 'unclosed").collect::<Vec<_>>(), vec!["red"]);
+
+    let d = Declaration::new("margin", "0 auto", false);
+    assert_eq!(d.value_tokens().collect::<Vec<_>>(), vec!["0", "auto"]);
+}
+
+#[test]
+fn pos_01() {
+    let mut t = DeclarationTokenizer::from("color:red; fill:green");
+    assert_eq!(t.pos(), 0);
+    t.next();
+    let saved = t.clone();
+    assert_eq!(t.pos(), 11);
+    assert_eq!(t.next().unwrap(), Declaration::new("fill", "green", false));
+    assert_eq!(t.pos(), 21);
+    assert!(t.next().is_none());
+
+    t = saved;
+    assert_eq!(t.next().unwrap(), Declaration::new("fill", "green", false));
+
+    let mut t = ValueTokenizer::from("url(a.png) red");
+    t.next();
+    assert_eq!(t.pos(), 10);
+    assert_eq!(t.next().unwrap(), ValueToken::Term("red"));
+    assert_eq!(t.pos(), 14);
+}
+
+#[test]
+fn span_01() {
+    let mut t = ValueTokenizer::from(" rgb(0, 0, 0) /* c */ url( 'a.png' ),5px");
+    assert_eq!(t.next_spanned().unwrap(), (ValueToken::Term("rgb(0, 0, 0)"), 1..13));
+    assert_eq!(t.next_spanned().unwrap(), (ValueToken::Url("a.png".into(), true), 22..36));
+    assert_eq!(t.next_spanned().unwrap(), (ValueToken::Term(","), 36..37));
+    assert_eq!(t.next_spanned().unwrap(), (ValueToken::Term("5px"), 37..40));
+    assert!(t.next_spanned().is_none());
+}
+
+#[test]
+fn parse_url_01() {
+    assert_eq!(parse_url("url(img.png)"), Some("img.png"));
+    assert_eq!(parse_url("url('img.png')"), Some("img.png"));
+    assert_eq!(parse_url("url(\"img.png\")"), Some("img.png"));
+    assert_eq!(parse_url("URL( \t'a b.png'\n)"), Some("a b.png"));
+    assert_eq!(parse_url("  url(  img.png  )  "), Some("img.png"));
+    assert_eq!(parse_url("url(a\\(b\\).png)"), Some("a\\(b\\).png"));
+    assert_eq!(parse_url("url()"), Some(""));
+}
+
+#[test]
+fn parse_url_02() {
+    assert_eq!(parse_url(""), None);
+    assert_eq!(parse_url("img.png"), None);
+    assert_eq!(parse_url("'img.png'"), None);
+    assert_eq!(parse_url("url(img.png"), None);
+    assert_eq!(parse_url("url(a b.png)"), None);
+    assert_eq!(parse_url("url(a.png) url(b.png)"), None);
+    assert_eq!(parse_url("myurl(img.png)"), None);
+}
+
 //tokenize!(tokenize_, "@unsupported { splines: reticulating } color: green",
 //    declare("color", "green")
 //);
@@ -153,3 +537,13 @@ tokenize!(tokenize_34, "*zoom:1;",
 //tokenize!(tokenize_, "\"this is a string]}\"\"[{\\\"'\";  /*should be parsed as a string but be ignored*/
 //    {{}}[]'';                     /*should be parsed as nested blocks and a string but be ignored*/
 //    color: red;", declare("color", "red"));
+
+#[test]
+fn escaped_name() {
+    let d = &parse_inline_declarations(r"\63 olor: red; \--a\:b: 1; fill: \72 ed")[..];
+    assert_eq!(d[0], declare("color", "red"));
+    assert_eq!(d[1].name, "--a:b");
+    // Values are not decoded.
+    assert_eq!(d[2].value, r"\72 ed");
+    assert!(matches!(d[2].name, std::borrow::Cow::Borrowed("fill")));
+}
@@ -0,0 +1,71 @@
+use simplecss::*;
+
+#[test]
+fn linear_angle() {
+    let g = Gradient::parse("linear-gradient(45deg, red 0%, #00f 100%)").unwrap();
+    assert_eq!(g.kind, GradientKind::Linear(GradientDirection::Angle(45.0)));
+    assert_eq!(g.stops, vec![
+        GradientStop { color: Color::new_rgba(255, 0, 0, 255), offset: Some(0.0) },
+        GradientStop { color: Color::new_rgba(0, 0, 255, 255), offset: Some(1.0) },
+    ]);
+}
+
+#[test]
+fn linear_angle_units() {
+    let angle = |text: &str| match Gradient::parse(text).unwrap().kind {
+        GradientKind::Linear(GradientDirection::Angle(angle)) => angle,
+        _ => panic!("not an angle"),
+    };
+
+    assert_eq!(angle("linear-gradient(0.5turn, red, blue)"), 180.0);
+    assert_eq!(angle("linear-gradient(100grad, red, blue)"), 90.0);
+    assert_eq!(angle("linear-gradient(-90DEG, red, blue)"), -90.0);
+    assert_eq!(angle("linear-gradient(red, blue)"), 180.0);
+    assert_eq!(angle("linear-gradient(to left, red, blue)"), 270.0);
+    assert_eq!(angle("linear-gradient(to top, red, blue)"), 0.0);
+}
+
+#[test]
+fn linear_corner() {
+    let g = Gradient::parse("linear-gradient(to right top, red, blue)").unwrap();
+    assert_eq!(g.kind, GradientKind::Linear(GradientDirection::Corner { top: true, left: false }));
+}
+
+#[test]
+fn linear_stops() {
+    let g = Gradient::parse("linear-gradient(rgba(255, 0, 0, 0.5) 10% 20%, blue)").unwrap();
+    let red = Color::new_rgba(255, 0, 0, 128);
+    assert_eq!(g.stops, vec![
+        GradientStop { color: red, offset: Some(0.1) },
+        GradientStop { color: red, offset: Some(0.2) },
+        GradientStop { color: Color::new_rgba(0, 0, 255, 255), offset: None },
+    ]);
+}
+
+#[test]
+fn radial() {
+    let g = Gradient::parse("radial-gradient(circle at center, red, blue 50%)").unwrap();
+    assert_eq!(g.kind, GradientKind::Radial);
+    assert_eq!(g.stops.len(), 2);
+
+    let g = Gradient::parse("radial-gradient(red, blue)").unwrap();
+    assert_eq!(g.stops.len(), 2);
+}
+
+#[test]
+fn invalid() {
+    assert_eq!(Gradient::parse("red"), None);
+    assert_eq!(Gradient::parse("url(#grad)"), None);
+    assert_eq!(Gradient::parse("linear-gradient(red)"), None);
+    assert_eq!(Gradient::parse("linear-gradient(45deg, red, blue"), None);
+    assert_eq!(Gradient::parse("linear-gradient(to top bottom, red, blue)"), None);
+    assert_eq!(Gradient::parse("linear-gradient(red 10px, blue)"), None);
+    assert_eq!(Gradient::parse("conic-gradient(red, blue)"), None);
+}
+
+#[test]
+fn declaration() {
+    let d = Declaration::new("fill", "linear-gradient(90deg, red, blue)", false);
+    assert_eq!(d.as_gradient().unwrap().kind, GradientKind::Linear(GradientDirection::Angle(90.0)));
+    assert_eq!(Declaration::new("fill", "red", false).as_gradient(), None);
+}
@@ -0,0 +1,39 @@
+use simplecss::*;
+
+#[test]
+fn units() {
+    assert_eq!(parse_length("5"), Some((5.0, LengthUnit::None)));
+    assert_eq!(parse_length("5px"), Some((5.0, LengthUnit::Px)));
+    assert_eq!(parse_length("5em"), Some((5.0, LengthUnit::Em)));
+    assert_eq!(parse_length("5rem"), Some((5.0, LengthUnit::Rem)));
+    assert_eq!(parse_length("5ex"), Some((5.0, LengthUnit::Ex)));
+    assert_eq!(parse_length("5pt"), Some((5.0, LengthUnit::Pt)));
+    assert_eq!(parse_length("5pc"), Some((5.0, LengthUnit::Pc)));
+    assert_eq!(parse_length("5cm"), Some((5.0, LengthUnit::Cm)));
+    assert_eq!(parse_length("5mm"), Some((5.0, LengthUnit::Mm)));
+    assert_eq!(parse_length("5in"), Some((5.0, LengthUnit::In)));
+    assert_eq!(parse_length("5%"), Some((5.0, LengthUnit::Percent)));
+    assert_eq!(parse_length("5PX"), Some((5.0, LengthUnit::Px)));
+}
+
+#[test]
+fn numbers() {
+    assert_eq!(parse_length("+1.5px"), Some((1.5, LengthUnit::Px)));
+    assert_eq!(parse_length("-.5em"), Some((-0.5, LengthUnit::Em)));
+    assert_eq!(parse_length("10.%"), Some((10.0, LengthUnit::Percent)));
+    assert_eq!(parse_length("  0  "), Some((0.0, LengthUnit::None)));
+}
+
+#[test]
+fn invalid() {
+    assert_eq!(parse_length(""), None);
+    assert_eq!(parse_length("px"), None);
+    assert_eq!(parse_length("-"), None);
+    assert_eq!(parse_length("5 px"), None);
+    assert_eq!(parse_length("5px 2px"), None);
+    assert_eq!(parse_length("5vw"), None);
+    assert_eq!(parse_length("5px2"), None);
+    assert_eq!(parse_length("1e3px"), None);
+    assert_eq!(parse_length("calc(5px)"), None);
+    assert_eq!(parse_length("'5px'"), None);
+}
@@ -0,0 +1,37 @@
+use simplecss::*;
+
+fn load(text: &str) -> OwnedStyleSheet {
+    // A temporary buffer, like the one a file was read into.
+    let text = text.to_string();
+    let mut sheet = StyleSheet::parse(&text);
+    sheet.set_origin(Origin::User);
+    sheet.into_owned()
+}
+
+#[test]
+fn into_owned() {
+    let owned = load("\
+        @import 'a.css';
+        b, a { color:red }
+        @supports (display: grid) { p { display: grid } }
+    ");
+
+    let sheet = owned.sheet();
+    assert_eq!(sheet.to_string(), "\
+@import \"a.css\";
+b { color:red; }
+a { color:red; }
+@supports (display: grid) { p { display:grid; } }");
+    assert_eq!(sheet.imports, vec![Import { href: "a.css", media: None }]);
+
+    let rules: Vec<_> = sheet.rules.iter().map(|r| (r.origin, r.source_order)).collect();
+    assert_eq!(rules, vec![(Origin::User, 0), (Origin::User, 1), (Origin::User, 2)]);
+}
+
+#[test]
+fn new() {
+    let owned = OwnedStyleSheet::from("a { color:red }".to_string());
+    assert_eq!(owned.text(), "a { color:red }");
+    assert_eq!(owned.sheet().rules[0].origin, Origin::Author);
+    assert_eq!(owned.clone().sheet().to_string(), "a { color:red; }");
+}
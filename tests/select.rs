@@ -36,6 +36,13 @@ impl simplecss::Element for XmlNode<'_, '_> {
         }
     }
 
+    fn attribute_matches_ignore_case(&self, local_name: &str, operator: AttributeOperator) -> bool {
+        match self.0.attribute(local_name) {
+            Some(value) => operator.matches_ignore_case(value),
+            None => false,
+        }
+    }
+
     fn pseudo_class_matches(&self, class: PseudoClass) -> bool {
         match class {
             PseudoClass::FirstChild => self.prev_sibling_element().is_none(),
@@ -44,6 +51,105 @@ impl simplecss::Element for XmlNode<'_, '_> {
     }
 }
 
+// Same as `XmlNode`, but uses byte-oriented local name matching.
+struct XmlBytesNode<'a, 'input: 'a>(roxmltree::Node<'a, 'input>);
+
+impl simplecss::Element for XmlBytesNode<'_, '_> {
+    fn parent_element(&self) -> Option<Self> {
+        self.0.parent_element().map(XmlBytesNode)
+    }
+
+    fn prev_sibling_element(&self) -> Option<Self> {
+        self.0.prev_siblings().filter(|n| n.is_element()).nth(0).map(XmlBytesNode)
+    }
+
+    fn has_local_name(&self, _: &str) -> bool {
+        unreachable!()
+    }
+
+    fn has_local_name_bytes(&self, local_name: &[u8]) -> bool {
+        self.0.tag_name().name().as_bytes() == local_name
+    }
+
+    fn attribute_matches(&self, local_name: &str, operator: AttributeOperator) -> bool {
+        assert!(!(local_name == "class" && matches!(operator, AttributeOperator::Contains(_))));
+        XmlNode(self.0).attribute_matches(local_name, operator)
+    }
+
+    fn has_class(&self, class: &str) -> bool {
+        match self.0.attribute("class") {
+            Some(value) => value.split_ascii_whitespace().any(|c| c == class),
+            None => false,
+        }
+    }
+
+    fn pseudo_class_matches(&self, class: PseudoClass) -> bool {
+        XmlNode(self.0).pseudo_class_matches(class)
+    }
+}
+
+// Same as `XmlNode`, but can represent a pseudo-element of a node.
+struct XmlPseudoNode<'a, 'input: 'a>(roxmltree::Node<'a, 'input>, Option<&'static str>);
+
+impl<'a, 'input: 'a> simplecss::Element for XmlPseudoNode<'a, 'input> {
+    fn parent_element(&self) -> Option<Self> {
+        XmlNode(self.0).parent_element().map(|n| XmlPseudoNode(n.0, None))
+    }
+
+    fn prev_sibling_element(&self) -> Option<Self> {
+        XmlNode(self.0).prev_sibling_element().map(|n| XmlPseudoNode(n.0, None))
+    }
+
+    fn has_local_name(&self, local_name: &str) -> bool {
+        XmlNode(self.0).has_local_name(local_name)
+    }
+
+    fn attribute_matches(&self, local_name: &str, operator: AttributeOperator) -> bool {
+        XmlNode(self.0).attribute_matches(local_name, operator)
+    }
+
+    fn pseudo_class_matches(&self, class: PseudoClass) -> bool {
+        XmlNode(self.0).pseudo_class_matches(class)
+    }
+
+    fn pseudo_element(&self) -> Option<&str> {
+        self.1
+    }
+}
+
+// Same as `XmlNode`, but can iterate children only forward.
+struct XmlForwardNode<'a, 'input: 'a>(roxmltree::Node<'a, 'input>);
+
+impl simplecss::Element for XmlForwardNode<'_, '_> {
+    fn parent_element(&self) -> Option<Self> {
+        self.0.parent_element().map(XmlForwardNode)
+    }
+
+    fn prev_sibling_element(&self) -> Option<Self> {
+        None
+    }
+
+    fn children(&self) -> Option<Vec<Self>> {
+        Some(self.0.children().filter(|n| n.is_element()).map(XmlForwardNode).collect())
+    }
+
+    fn is_same(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+
+    fn has_local_name(&self, local_name: &str) -> bool {
+        XmlNode(self.0).has_local_name(local_name)
+    }
+
+    fn attribute_matches(&self, local_name: &str, operator: AttributeOperator) -> bool {
+        XmlNode(self.0).attribute_matches(local_name, operator)
+    }
+
+    fn pseudo_class_matches(&self, _: PseudoClass) -> bool {
+        false
+    }
+}
+
 macro_rules! match_single {
     ($doc:expr, $selector:expr) => {{
         let nodes = XmlNode($doc.root_element()).select($selector);
@@ -419,8 +525,742 @@ fn select_30() {
     assert_eq!(nodes[1].attribute("id").unwrap(), "p1");
 }
 
+#[test]
+fn select_31() {
+    let doc = roxmltree::Document::parse("\
+<div id='div1'>
+    <p id='p1'/>
+    <p id='p2'/>
+    <p id='p3'/>
+    <p id='p4'/>
+    <p id='p5'/>
+</div>
+").unwrap();
+
+    let select = |text| {
+        XmlNode(doc.root_element()).select(text).iter()
+            .map(|n| n.attribute("id").unwrap())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(select("p:nth-child(2n+1)"), ["p1", "p3", "p5"]);
+    assert_eq!(select("p:nth-child(odd)"), ["p1", "p3", "p5"]);
+    assert_eq!(select("p:nth-child(even)"), ["p2", "p4"]);
+    assert_eq!(select("p:nth-child(-n+3)"), ["p1", "p2", "p3"]);
+    assert_eq!(select("p:nth-child(n)"), ["p1", "p2", "p3", "p4", "p5"]);
+    assert_eq!(select("p:nth-child(n+4)"), ["p4", "p5"]);
+    assert_eq!(select("p:nth-child(3)"), ["p3"]);
+    assert_eq!(select("p:nth-child(-2n+10)"), ["p2", "p4"]);
+    assert!(select("p:nth-child(0)").is_empty());
+}
+
+#[test]
+fn select_nth_child_forward_only() {
+    let doc = roxmltree::Document::parse("\
+<div id='div1'>
+    <p id='p1'/>
+    <p id='p2'/>
+    <p id='p3'/>
+</div>
+").unwrap();
+
+    let select = |text| {
+        let selector = Selector::parse(text).unwrap();
+        doc.descendants()
+            .filter(|n| n.is_element() && selector.matches(&XmlForwardNode(*n)))
+            .map(|n| n.attribute("id").unwrap())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(select("p:nth-child(2)"), ["p2"]);
+    assert_eq!(select("p:nth-child(odd)"), ["p1", "p3"]);
+    assert_eq!(select("p + p"), ["p2", "p3"]);
+    assert_eq!(select("#p1 + p"), ["p2"]);
+    assert!(select("div:nth-child(2)").is_empty());
+}
+
+#[test]
+fn select_32() {
+    let doc = roxmltree::Document::parse("\
+<div id='div1'>
+    <p id='p1' class='a'/>
+    <p id='p2' title='x'/>
+    <span id='span1' class='a'/>
+</div>
+").unwrap();
+
+    let select = |text| {
+        XmlNode(doc.root_element()).select(text).iter()
+            .map(|n| n.attribute("id").unwrap())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(select("p:not(.a)"), ["p2"]);
+    assert_eq!(select("div > :not(p)"), ["span1"]);
+    assert_eq!(select(":not([title]):not(div)"), ["p1", "span1"]);
+    assert_eq!(select("div :not(#p1)"), ["p2", "span1"]);
+    assert_eq!(select("p:not(:first-child)"), ["p2"]);
+    assert_eq!(select("p:not(:nth-child(2))"), ["p1"]);
+    assert!(select("p:not(*)").is_empty());
+}
+
+#[test]
+fn pseudo_elements() {
+    let doc = roxmltree::Document::parse("<div id='div1'><p id='p1'/></div>").unwrap();
+    let p = XmlNode(doc.descendants().find(|n| n.has_tag_name("p")).unwrap());
+
+    for (text, name, string) in &[
+        ("p::before", "before", "p::before"),
+        ("div > p:after", "after", "div > p::after"),
+        ("::selection", "selection", "*::selection"),
+        ("div ::marker", "marker", "div *::marker"),
+    ] {
+        let selector = Selector::parse(text).unwrap();
+        assert_eq!(selector.pseudo_element(), Some(*name));
+        assert_eq!(selector.to_string(), *string);
+        assert!(!selector.matches(&p));
+    }
+
+    assert_eq!(Selector::parse("p").unwrap().pseudo_element(), None);
+    assert!(Selector::parse("p::before span").is_none());
+    assert!(Selector::parse("p::before:hover").is_none());
+    assert!(Selector::parse("p:not(::before)").is_none());
+}
+
+#[test]
+fn pseudo_elements_matching() {
+    let doc = roxmltree::Document::parse("<div id='div1'><p id='p1' class='a'/></div>").unwrap();
+    let p = doc.descendants().find(|n| n.has_tag_name("p")).unwrap();
+    let before = XmlPseudoNode(p, Some("before"));
+    let after = XmlPseudoNode(p, Some("after"));
+
+    for text in &["p::before", "div > p.a:before", "::before", "div ::before"] {
+        let selector = Selector::parse(text).unwrap();
+        assert!(selector.matches(&before));
+        assert!(!selector.matches(&after));
+        assert!(!selector.matches(&XmlPseudoNode(p, None)));
+    }
+
+    assert!(!Selector::parse("span::before").unwrap().matches(&before));
+    assert!(!Selector::parse("p").unwrap().matches(&before));
+    assert!(Selector::parse("p").unwrap().matches(&XmlPseudoNode(p, None)));
+}
+
+#[test]
+fn select_scope() {
+    let doc = roxmltree::Document::parse("\
+<svg id='svg1'>
+    <g id='g1'>
+        <g id='g2'/>
+    </g>
+</svg>
+").unwrap();
+
+    assert_eq!(match_single!(doc, ":scope"), "svg1");
+    assert_eq!(match_single!(doc, "svg:scope"), "svg1");
+    assert_eq!(match_single!(doc, ":scope > g"), "g1");
+    match_none!(doc, "g:scope");
+    assert_eq!(Selector::parse(":scope > g").unwrap().to_string(), "*:scope > g");
+}
+
+#[test]
+fn select_root() {
+    let doc = roxmltree::Document::parse("\
+<svg id='svg1'>
+    <g id='g1'>
+        <g id='g2'/>
+    </g>
+</svg>
+").unwrap();
+
+    assert_eq!(match_single!(doc, ":root"), "svg1");
+    assert_eq!(match_single!(doc, "svg:root"), "svg1");
+    assert_eq!(match_single!(doc, ":root > g"), "g1");
+    assert_eq!(match_single!(doc, ":root g g"), "g2");
+    match_none!(doc, "g:root");
+    match_none!(doc, ":not(:root) > svg");
+    assert_eq!(Selector::parse(":root").unwrap().specificity(), Specificity([0, 1, 0]));
+    assert_eq!(Selector::parse(":root > g").unwrap().to_string(), "*:root > g");
+}
+
+#[test]
+fn select_lang() {
+    let doc = roxmltree::Document::parse("\
+<div id='div1' lang='en-US'>
+    <p id='p1'/>
+    <p id='p2' lang='FR-ca'>
+        <span id='span1'/>
+        <span id='span2' lang=''/>
+    </p>
+</div>
+").unwrap();
+
+    let select = |text| {
+        XmlNode(doc.root_element()).select(text).iter()
+            .map(|n| n.attribute("id").unwrap())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(select(":lang(en)"), ["div1", "p1"]);
+    assert_eq!(select("p:lang(en-us)"), ["p1"]);
+    assert_eq!(select(":lang(fr)"), ["p2", "span1"]);
+    assert_eq!(select(":lang(fr-CA)"), ["p2", "span1"]);
+    assert!(select(":lang(e)").is_empty());
+    assert!(select(":lang(en-US-x)").is_empty());
+    assert_eq!(select("span:not(:lang(fr))"), ["span2"]);
+}
+
+#[test]
+fn select_is_where() {
+    let doc = roxmltree::Document::parse("\
+<div id='div1'>
+    <p id='p1' class='a'/>
+    <p id='p2' title='x'/>
+    <span id='span1' class='b'/>
+</div>
+").unwrap();
+
+    let select = |text| {
+        XmlNode(doc.root_element()).select(text).iter()
+            .map(|n| n.attribute("id").unwrap())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(select(":is(.a, .b)"), ["p1", "span1"]);
+    assert_eq!(select("div > :where(p[title], span)"), ["p2", "span1"]);
+    assert_eq!(select("p:is(:not(.a))"), ["p2"]);
+    assert_eq!(select(":is(div) :where(:is(p, span).b)"), ["span1"]);
+    assert!(select(":is(h1)").is_empty());
+
+    let selector = Selector::parse("p:is( .a , span ):where(#b)").unwrap();
+    assert_eq!(selector.to_string(), "p:is(*[class~='a'], span):where(*#b)");
+    assert_eq!(format!("{:#}", selector), "p:is(*[class~='a'],span):where(*#b)");
+}
+
+#[test]
+fn select_is_where_invalid() {
+    assert_eq!(Selector::try_parse("p:is(div p)").unwrap_err().1, Error::UnexpectedCombinator);
+    assert_eq!(Selector::try_parse(":where(a, b > c)").unwrap_err().1, Error::UnexpectedCombinator);
+    assert!(Selector::parse(":is()").is_none());
+    assert!(Selector::parse(":is(a,)").is_none());
+    assert!(Selector::parse(":is(a b)").is_none());
+    assert!(Selector::parse(":is(::before)").is_none());
+    assert!(Selector::parse(":where(:unknown)").is_none());
+}
+
+#[test]
+fn select_not_invalid() {
+    assert!(Selector::parse("p:not(div p)").is_none());
+    assert!(Selector::parse("p:not(div > p)").is_none());
+    assert!(Selector::parse("p:not(p.a)").is_none());
+    assert!(Selector::parse("p:not(:not(p))").is_none());
+    assert!(Selector::parse("p:not(a, b)").is_none());
+    assert!(Selector::parse("p:not()").is_none());
+    assert!(Selector::parse("p:not(*.a)").is_none());
+    assert!(Selector::parse("p:not(*[title])").is_none());
+    assert!(Selector::parse("p:not(svg|*.a)").is_none());
+    assert!(Selector::parse("p:not(svg|a.b)").is_none());
+
+    // A single type or universal selector is still allowed.
+    assert!(Selector::parse("p:not(*)").is_some());
+    assert!(Selector::parse("p:not(svg|*)").is_some());
+    assert!(Selector::parse("p:not(.a)").is_some());
+}
+
 #[test]
 fn to_string() {
     let selectors = Selector::parse("a > b").unwrap();
     assert_eq!(selectors.to_string(), "a > b");
 }
+
+#[test]
+fn to_string_not() {
+    let selectors = Selector::parse("a:not( .b ):not(c)").unwrap();
+    assert_eq!(selectors.to_string(), "a:not(*[class~='b']):not(c)");
+}
+
+#[test]
+fn to_string_nth_child() {
+    let selectors = Selector::parse("li:nth-child(odd) + li:nth-child(-n+3):nth-child(4)").unwrap();
+    assert_eq!(selectors.to_string(), "li:nth-child(2n+1) + li:nth-child(-1n+3):nth-child(4)");
+}
+
+#[test]
+fn selector_list_to_string() {
+    let list = SelectorList::parse("h1,h2 , .x").unwrap();
+    assert_eq!(list.selectors.len(), 3);
+    assert_eq!(list.to_string(), "h1, h2, *[class~='x']");
+    assert_eq!(format!("{:#}", list), "h1,h2,*[class~='x']");
+
+    let text = list.to_string();
+    let list = SelectorList::parse(&text).unwrap();
+    assert_eq!(list.to_string(), text);
+}
+
+#[test]
+fn consecutive_combinators() {
+    assert_eq!(Selector::try_parse("a > > b").unwrap_err(),
+               (TextPos::new(1, 5, 4), Error::UnexpectedCombinator));
+    assert_eq!(Selector::try_parse("a + + b").unwrap_err(),
+               (TextPos::new(1, 5, 4), Error::UnexpectedCombinator));
+    assert_eq!(Selector::try_parse("a >~ b").unwrap_err(),
+               (TextPos::new(1, 4, 3), Error::UnexpectedCombinator));
+    assert_eq!(Selector::try_parse("a\n  +\n  > b").unwrap_err(),
+               (TextPos::new(3, 3, 8), Error::UnexpectedCombinator));
+    assert!(Selector::try_parse("a > b").is_ok());
+}
+
+#[test]
+fn try_from_str() {
+    use std::convert::TryFrom;
+
+    assert_eq!(Selector::try_from(" div > p.x ").unwrap(), Selector::parse("div > p.x").unwrap());
+    assert_eq!(Selector::try_from("a { color: red }").unwrap_err(),
+               Error::UnexpectedData(TextPos::new(1, 3, 2)));
+    assert_eq!(Selector::try_from("a > > b").unwrap_err(), Error::UnexpectedCombinator);
+    assert_eq!(Selector::try_from("").unwrap_err(), Error::SelectorMissing);
+}
+
+#[test]
+fn error_pos_multibyte() {
+    // Columns are counted in characters and not in bytes.
+    assert_eq!(Selector::try_parse("日本 > > b").unwrap_err(),
+               (TextPos::new(1, 6, 9), Error::UnexpectedCombinator));
+    assert_eq!(Selector::try_parse("ф\n日本 > > b").unwrap_err(),
+               (TextPos::new(2, 6, 12), Error::UnexpectedCombinator));
+}
+
+#[test]
+fn attribute_contains() {
+    let op = AttributeOperator::Contains("a");
+    assert!(op.matches("a"));
+    assert!(op.matches("b a c"));
+    assert!(op.matches("  a  "));
+    assert!(op.matches("b\ta\nc"));
+    assert!(op.matches("a a"));
+    assert!(!op.matches(""));
+    assert!(!op.matches("ab"));
+    assert!(!op.matches("b-a"));
+
+    assert!(!AttributeOperator::Contains("").matches(""));
+    assert!(!AttributeOperator::Contains("").matches("a  b"));
+    assert!(!AttributeOperator::Contains("a b").matches("a b"));
+}
+
+#[test]
+fn attribute_empty_value() {
+    // `=` with an empty value matches only an empty attribute value.
+    assert!(AttributeOperator::Matches("").matches(""));
+    assert!(!AttributeOperator::Matches("").matches("a"));
+    assert!(!AttributeOperator::Matches("").matches(" "));
+
+    // `~=` with an empty value never matches.
+    assert!(!AttributeOperator::Contains("").matches(""));
+    assert!(!AttributeOperator::Contains("").matches("a"));
+
+    // `|=` with an empty value matches an empty value or a value starting with `-`.
+    assert!(AttributeOperator::StartsWith("").matches(""));
+    assert!(AttributeOperator::StartsWith("").matches("-a"));
+    assert!(!AttributeOperator::StartsWith("").matches("a"));
+    assert!(!AttributeOperator::StartsWith("").matches("a-"));
+
+    let doc = roxmltree::Document::parse("\
+<div id='div1'>
+    <p id='p1' title=''/>
+    <p id='p2' title='a'/>
+</div>
+").unwrap();
+
+    assert_eq!(match_single!(doc, "p[title='']"), "p1");
+    assert_eq!(match_single!(doc, "p[title=\"\"]"), "p1");
+    match_none!(doc, "p[title~='']");
+    assert_eq!(match_single!(doc, "p[title|='']"), "p1");
+}
+
+#[test]
+fn select_attribute_quotes() {
+    let doc = roxmltree::Document::parse("\
+<form id='form1'>
+    <input id='input1' type='checkbox'/>
+    <input id='input2' type='text'/>
+</form>
+").unwrap();
+
+    assert_eq!(match_single!(doc, "[type=checkbox]"), "input1");
+    assert_eq!(match_single!(doc, "[type='checkbox']"), "input1");
+    assert_eq!(match_single!(doc, "[type=\"checkbox\"]"), "input1");
+    assert_eq!(match_single!(doc, "input[ type = text ]"), "input2");
+}
+
+#[test]
+fn select_class_whitespace() {
+    let doc = roxmltree::Document::parse("\
+<div id='div1'>
+    <p id='p1' class=' a&#x9;b '/>
+    <p id='p2' class='b b'/>
+    <p id='p3' class=''/>
+</div>
+").unwrap();
+
+    assert_eq!(match_single!(doc, "[class~=a]"), "p1");
+    assert_eq!(match_single!(doc, ".a.b"), "p1");
+    assert_eq!(XmlNode(doc.root_element()).select("[class~='b']").len(), 2);
+    match_none!(doc, "[class~='']");
+    match_none!(doc, "[class~='a b']");
+}
+
+#[test]
+fn selector_list_invalid() {
+    assert!(SelectorList::parse("h1, > h2").is_none());
+    assert!(SelectorList::parse("h1,").is_none());
+}
+
+#[test]
+fn selector_list_comments() {
+    let list = SelectorList::parse("div/*c*/,h1,/*c*/p/*c*/,/*c*/a").unwrap();
+    assert_eq!(list.to_string(), "div, h1, p, a");
+
+    let style = StyleSheet::parse("div/*c*/>h1,/*c*/p/*c*/+a { color:red }");
+    assert_eq!(style.to_string(), "div > h1 { color:red; }\np + a { color:red; }");
+}
+
+#[test]
+fn conformance() {
+    let doc = roxmltree::Document::parse(simplecss::conformance::TREE).unwrap();
+    simplecss::conformance::check_element_conformance(|id| {
+        XmlNode(doc.descendants().find(|n| n.attribute("id") == Some(id)).unwrap())
+    });
+}
+
+#[test]
+fn local_name_bytes() {
+    let doc = roxmltree::Document::parse("\
+<div id='div1'>
+    <p id='p1'/>
+    <g id='g1'>
+        <p id='p2'/>
+        <rect id='rect1'/>
+    </g>
+</div>
+").unwrap();
+
+    for text in &["div", "p", "div p", "g > p + rect", "*", "rect:first-child", "q"] {
+        let selector = Selector::parse(text).unwrap();
+        for node in doc.descendants().filter(|n| n.is_element()) {
+            assert_eq!(selector.matches(&XmlNode(node)), selector.matches(&XmlBytesNode(node)));
+        }
+    }
+}
+
+#[test]
+fn has_class() {
+    let doc = roxmltree::Document::parse("\
+<div id='div1' class='a b'>
+    <p id='p1' class='b'/>
+    <p id='p2' class='ab'/>
+    <p id='p3' class=''/>
+</div>
+").unwrap();
+
+    for text in &[".a", ".b", "div.a > .b", "[class~=b]", "[class=b]", ".ab", ".a.b", "p"] {
+        let selector = Selector::parse(text).unwrap();
+        for node in doc.descendants().filter(|n| n.is_element()) {
+            assert_eq!(selector.matches(&XmlNode(node)), selector.matches(&XmlBytesNode(node)));
+        }
+    }
+}
+
+#[test]
+fn rules_for() {
+    let doc = roxmltree::Document::parse("<div id='div1'><p id='p1' class='a'/></div>").unwrap();
+    let p = doc.descendants().find(|n| n.has_tag_name("p")).unwrap();
+
+    let sheet = StyleSheet::parse("p.a { color:red } div p { color:green } b { color:blue } * { width:5px }");
+    let selectors: Vec<_> = sheet.rules_for(&XmlNode(p)).map(|r| r.selector.to_string()).collect();
+    assert_eq!(selectors, vec!["*", "div p", "p[class~='a']"]);
+
+    let matched = sheet.rules.iter().filter(|r| r.matches(&XmlNode(p))).count();
+    assert_eq!(matched, 3);
+}
+
+#[test]
+fn declarations_for() {
+    let doc = roxmltree::Document::parse("<div id='div1'><p id='p1' class='a'/></div>").unwrap();
+    let p = XmlNode(doc.descendants().find(|n| n.has_tag_name("p")).unwrap());
+
+    let sheet = StyleSheet::parse("
+        p.a { color:red; fill:red }
+        p { color:green; stroke:green !important; width:1px }
+        * { width:5px; stroke:blue; fill:blue !important }
+        p { width:2px }
+    ");
+    let declarations: Vec<_> = sheet.declarations_for(&p).iter()
+        .map(|d| (d.name.as_ref(), d.value)).collect();
+    assert_eq!(declarations, vec![
+        ("width", "2px"),
+        ("stroke", "green"),
+        ("fill", "blue"),
+        ("color", "red"),
+    ]);
+
+    let div = XmlNode(doc.root_element());
+    assert!(StyleSheet::parse("p { color:red }").declarations_for(&div).is_empty());
+}
+
+#[test]
+fn declarations_for_origins() {
+    let doc = roxmltree::Document::parse("<div id='div1'><p id='p1' class='a'/></div>").unwrap();
+    let p = XmlNode(doc.descendants().find(|n| n.has_tag_name("p")).unwrap());
+
+    let mut sheet = StyleSheet::parse("
+        p { color:black; fill:black !important; width:1px }
+    ");
+    sheet.set_origin(Origin::UserAgent);
+
+    let user = StyleSheet::parse("
+        * { color:green !important; stroke:green; width:2px !important; opacity:0.5 }
+    ");
+    for mut rule in user.rules {
+        rule.origin = Origin::User;
+        sheet.rules.push(rule);
+    }
+
+    sheet.parse_more("
+        p.a { color:red !important; fill:red !important; stroke:red; width:3px !important; opacity:1 }
+    ");
+
+    let declarations: Vec<_> = sheet.declarations_for(&p).iter()
+        .map(|d| (d.name.as_ref(), d.value)).collect();
+    assert_eq!(declarations, vec![
+        ("color", "green"),
+        ("stroke", "red"),
+        ("width", "2px"),
+        ("opacity", "1"),
+        ("fill", "black"),
+    ]);
+}
+
+#[test]
+fn case_insensitive() {
+    let doc = roxmltree::Document::parse("\
+<div id='div1'>
+    <p id='p1' class='Warn' title='Text'/>
+</div>
+").unwrap();
+
+    let p1 = XmlNode(doc.descendants().find(|n| n.attribute("id") == Some("p1")).unwrap());
+    let ctx = MatchingContext { case_sensitive: false };
+
+    let selector = Selector::parse("DIV > P[TITLE=Text].Warn").unwrap();
+    assert!(!selector.matches(&p1));
+    assert!(selector.matches_with_context(&p1, &ctx));
+
+    // Classes, IDs and attribute values are still case-sensitive.
+    assert!(!Selector::parse("p.warn").unwrap().matches_with_context(&p1, &ctx));
+    assert!(!Selector::parse("#P1").unwrap().matches_with_context(&p1, &ctx));
+    assert!(!Selector::parse("[title=text]").unwrap().matches_with_context(&p1, &ctx));
+}
+
+#[test]
+fn escapes() {
+    let doc = roxmltree::Document::parse(r#"
+<div id='div1'>
+    <p id='p1' class='&amp;B' title='a"b'/>
+    <p id='p&quot;2' class='x:y'/>
+    <p id='p3' class='md:flex w-1/2'/>
+</div>
+"#).unwrap();
+
+    assert_eq!(match_single!(doc, r".\26 B"), "p1");
+    assert_eq!(match_single!(doc, r".\000026B"), "p1");
+    assert_eq!(match_single!(doc, r"\70.\26 B"), "p1");
+    assert_eq!(match_single!(doc, r#"[title='a\"b']"#), "p1");
+    assert_eq!(match_single!(doc, r#"#p\"2"#), "p\"2");
+    assert_eq!(match_single!(doc, r".x\:y"), "p\"2");
+    assert_eq!(match_single!(doc, r".md\:flex"), "p3");
+    assert_eq!(match_single!(doc, r".w-1\/2"), "p3");
+    assert_eq!(match_single!(doc, r".md\3A flex.w-1\2f 2"), "p3");
+    match_none!(doc, r".\26");
+}
+
+#[test]
+fn unescape_01() {
+    assert_eq!(unescape(""), "");
+    assert_eq!(unescape("abc"), "abc");
+    assert_eq!(unescape(r"\26 B"), "&B");
+    assert_eq!(unescape(r"\26  B"), "& B");
+    assert_eq!(unescape("\\26\r\nB"), "&B");
+    assert_eq!(unescape(r"\000026B"), "&B");
+    assert_eq!(unescape(r"\0000261"), "&1");
+    assert_eq!(unescape(r#"a\"b"#), "a\"b");
+    assert_eq!(unescape(r"a\\b"), "a\\b");
+    assert_eq!(unescape(r"\0"), "\u{FFFD}");
+    assert_eq!(unescape(r"\D800"), "\u{FFFD}");
+    assert_eq!(unescape(r"\110000"), "\u{FFFD}");
+    assert_eq!(unescape("a\\\nb"), "ab");
+    assert_eq!(unescape("a\\"), "a");
+}
+
+#[test]
+fn components_01() {
+    let selector = Selector::try_parse("div#main .a.b + *[lang|='en']:first-child:not(.c)::before").unwrap();
+    let components: Vec<_> = selector.components().collect();
+    assert_eq!(components.len(), 3);
+
+    let (div, combinator) = components[0];
+    assert_eq!(div.local_name(), Some("div"));
+    assert_eq!(div.ids().collect::<Vec<_>>(), vec!["main"]);
+    assert_eq!(div.classes().count(), 0);
+    assert_eq!(combinator, Some(Combinator::Descendant));
+
+    let (classes, combinator) = components[1];
+    assert_eq!(classes.local_name(), None);
+    assert_eq!(classes.classes().collect::<Vec<_>>(), vec!["a", "b"]);
+    assert_eq!(classes.attributes().count(), 0);
+    assert_eq!(combinator, Some(Combinator::AdjacentSibling));
+
+    let (last, combinator) = components[2];
+    assert_eq!(last.local_name(), None);
+    assert_eq!(last.attributes().collect::<Vec<_>>(), vec![("lang", AttributeOperator::StartsWith("en"))]);
+    assert_eq!(last.pseudo_classes().collect::<Vec<_>>(), vec![PseudoClass::FirstChild]);
+    let negations: Vec<_> = last.negations().collect();
+    assert_eq!(negations.len(), 1);
+    assert_eq!(negations[0].classes().collect::<Vec<_>>(), vec!["c"]);
+    assert_eq!(combinator, None);
+
+    assert_eq!(selector.pseudo_element(), Some("before"));
+}
+
+#[test]
+fn components_ids() {
+    let selector = Selector::parse("#a[id=b][id]").unwrap();
+    let (compound, _) = selector.components().next().unwrap();
+    assert_eq!(compound.ids().collect::<Vec<_>>(), vec!["a"]);
+    assert_eq!(compound.attributes().collect::<Vec<_>>(), vec![
+        ("id", AttributeOperator::Matches("b")),
+        ("id", AttributeOperator::Exists),
+    ]);
+    assert_eq!(selector.to_string(), "*#a[id='b'][id]");
+}
+
+#[test]
+fn select_column_combinator() {
+    let doc = roxmltree::Document::parse("<table id='t'><col id='c'/><td id='td'/></table>").unwrap();
+    match_none!(doc, "col || td");
+
+    let selector = Selector::parse("col||td").unwrap();
+    assert_eq!(selector.to_string(), "col || td");
+    assert_eq!(format!("{:#}", selector), "col||td");
+    assert_eq!(selector.components().next().unwrap().1, Some(Combinator::Column));
+}
+
+#[test]
+fn select_attribute_ignore_case() {
+    let doc = roxmltree::Document::parse("\
+<div id='div1'>
+    <p id='p1' lang='en-US'/>
+    <input id='input1' type='Submit' class='Big red'/>
+</div>
+").unwrap();
+
+    assert_eq!(match_single!(doc, "[lang|='EN' i]"), "p1");
+    match_none!(doc, "[lang|='EN']");
+    match_none!(doc, "[lang|='EN' s]");
+    assert_eq!(match_single!(doc, "[type=submit i]"), "input1");
+    match_none!(doc, "[type=submit]");
+    assert_eq!(match_single!(doc, "[class~=big i]"), "input1");
+    match_none!(doc, ".big");
+    match_none!(doc, "[lang|='e' i]");
+
+    let selector = Selector::parse("[type=submit i][class~='big' s]").unwrap();
+    assert_eq!(selector.to_string(), "*[type='submit' i][class~='big']");
+    assert_eq!(selector.components().next().unwrap().0.classes().collect::<Vec<_>>(), vec!["big"]);
+}
+
+#[test]
+fn selector_hash() {
+    use std::collections::HashSet;
+
+    let style = StyleSheet::parse("a > b { color:red } a>b { fill:red } a b { color:red } .c { } .c { color:red }");
+    let selectors: HashSet<_> = style.rules.iter().map(|r| r.selector.clone()).collect();
+    assert_eq!(selectors.len(), 3);
+    assert!(selectors.contains(&Selector::parse("a > b").unwrap()));
+    assert!(selectors.contains(&Selector::parse("a b").unwrap()));
+    assert!(!selectors.contains(&Selector::parse("a + b").unwrap()));
+}
+
+// Same as `XmlNode`, but resolves namespace prefixes using a style sheet.
+struct XmlNsNode<'a, 'input: 'a, 's>(roxmltree::Node<'a, 'input>, &'s StyleSheet<'s>);
+
+impl simplecss::Element for XmlNsNode<'_, '_, '_> {
+    fn parent_element(&self) -> Option<Self> {
+        self.0.parent_element().map(|n| XmlNsNode(n, self.1))
+    }
+
+    fn prev_sibling_element(&self) -> Option<Self> {
+        XmlNode(self.0).prev_sibling_element().map(|n| XmlNsNode(n.0, self.1))
+    }
+
+    fn has_namespace_prefix(&self, prefix: &str) -> bool {
+        if prefix.is_empty() {
+            return self.0.tag_name().namespace().is_none();
+        }
+
+        let uri = self.1.namespace_uri(Some(prefix));
+        uri.is_some() && uri == self.0.tag_name().namespace()
+    }
+
+    fn has_local_name(&self, local_name: &str) -> bool {
+        XmlNode(self.0).has_local_name(local_name)
+    }
+
+    fn attribute_matches(&self, local_name: &str, operator: AttributeOperator) -> bool {
+        XmlNode(self.0).attribute_matches(local_name, operator)
+    }
+
+    fn pseudo_class_matches(&self, class: PseudoClass) -> bool {
+        XmlNode(self.0).pseudo_class_matches(class)
+    }
+}
+
+#[test]
+fn select_namespace() {
+    let doc = roxmltree::Document::parse("\
+<root xmlns:svg='http://www.w3.org/2000/svg'>
+    <svg:rect id='rect1'/>
+    <rect id='rect2'/>
+</root>
+").unwrap();
+
+    let style = StyleSheet::parse("\
+@namespace svg url(http://www.w3.org/2000/svg);
+@namespace html 'http://www.w3.org/1999/xhtml';
+svg|rect { fill:green }
+");
+
+    let select = |text: &str| -> Vec<&str> {
+        let selector = Selector::parse(text).unwrap();
+        doc.descendants()
+            .filter(|n| n.is_element() && selector.matches(&XmlNsNode(*n, &style)))
+            .map(|n| n.attribute("id").unwrap())
+            .collect()
+    };
+
+    assert_eq!(select("svg|rect"), vec!["rect1"]);
+    assert_eq!(select("|rect"), vec!["rect2"]);
+    assert_eq!(select("*|rect"), vec!["rect1", "rect2"]);
+    assert_eq!(select("rect"), vec!["rect1", "rect2"]);
+    assert_eq!(select("root > svg|*"), vec!["rect1"]);
+    assert!(select("html|rect").is_empty());
+    assert!(select("unknown|rect").is_empty());
+
+    // Namespaces are ignored by default.
+    assert_eq!(match_single!(doc, "root > |*:first-child"), "rect1");
+
+    let selector = Selector::parse("svg|rect").unwrap();
+    let components: Vec<_> = selector.components().collect();
+    assert_eq!(components[0].0.namespace_prefix(), Some("svg"));
+    assert_eq!(components[0].0.local_name(), Some("rect"));
+    assert_eq!(selector.to_string(), "svg|rect");
+    assert_eq!(Selector::parse("*|*").unwrap().to_string(), "*|*");
+}
@@ -23,43 +23,43 @@ tokenize!(tokenize_02, "div",
 );
 
 tokenize!(tokenize_03, "#div",
-    SelectorToken::IdSelector("div")
+    SelectorToken::IdSelector("div".into())
 );
 
 tokenize!(tokenize_04, ".div",
-    SelectorToken::ClassSelector("div")
+    SelectorToken::ClassSelector("div".into())
 );
 
 tokenize!(tokenize_05, "[id]",
-    SelectorToken::AttributeSelector("id", AttributeOperator::Exists)
+    SelectorToken::AttributeSelector("id", AttributeOperator::Exists, false, false)
 );
 
 tokenize!(tokenize_06, "[id=test]",
-    SelectorToken::AttributeSelector("id", AttributeOperator::Matches("test"))
+    SelectorToken::AttributeSelector("id", AttributeOperator::Matches("test"), false, false)
 );
 
 tokenize!(tokenize_07, "[id~=test]",
-    SelectorToken::AttributeSelector("id", AttributeOperator::Contains("test"))
+    SelectorToken::AttributeSelector("id", AttributeOperator::Contains("test"), false, false)
 );
 
 tokenize!(tokenize_08, "[id|=test]",
-    SelectorToken::AttributeSelector("id", AttributeOperator::StartsWith("test"))
+    SelectorToken::AttributeSelector("id", AttributeOperator::StartsWith("test"), false, false)
 );
 
 tokenize!(tokenize_09, "[id='test']",
-    SelectorToken::AttributeSelector("id", AttributeOperator::Matches("test"))
+    SelectorToken::AttributeSelector("id", AttributeOperator::Matches("test"), true, false)
 );
 
 tokenize!(tokenize_10, "[id=\"test\"]",
-    SelectorToken::AttributeSelector("id", AttributeOperator::Matches("test"))
+    SelectorToken::AttributeSelector("id", AttributeOperator::Matches("test"), true, false)
 );
 
 tokenize!(tokenize_11, "[id='te\\'st']",
-    SelectorToken::AttributeSelector("id", AttributeOperator::Matches("te\\'st"))
+    SelectorToken::AttributeSelector("id", AttributeOperator::Matches("te\\'st"), true, false)
 );
 
 tokenize!(tokenize_12, "[id=\"te\\\"st\"]",
-    SelectorToken::AttributeSelector("id", AttributeOperator::Matches("te\\\"st"))
+    SelectorToken::AttributeSelector("id", AttributeOperator::Matches("te\\\"st"), true, false)
 );
 
 tokenize!(tokenize_13, "div:first-child",
@@ -112,7 +112,7 @@ tokenize!(tokenize_20, "div > p",
 tokenize!(tokenize_21, "div .p",
     SelectorToken::TypeSelector("div"),
     SelectorToken::DescendantCombinator,
-    SelectorToken::ClassSelector("p")
+    SelectorToken::ClassSelector("p".into())
 );
 
 tokenize!(tokenize_22, "div *",
@@ -124,13 +124,13 @@ tokenize!(tokenize_22, "div *",
 tokenize!(tokenize_23, "div #p",
     SelectorToken::TypeSelector("div"),
     SelectorToken::DescendantCombinator,
-    SelectorToken::IdSelector("p")
+    SelectorToken::IdSelector("p".into())
 );
 
 tokenize!(tokenize_24, "div [id]",
     SelectorToken::TypeSelector("div"),
     SelectorToken::DescendantCombinator,
-    SelectorToken::AttributeSelector("id", AttributeOperator::Exists)
+    SelectorToken::AttributeSelector("id", AttributeOperator::Exists, false, false)
 );
 
 tokenize!(tokenize_25, "div :link",
@@ -181,33 +181,33 @@ tokenize!(tokenize_33, "div ,",
 
 tokenize!(tokenize_34, "div.test",
     SelectorToken::TypeSelector("div"),
-    SelectorToken::ClassSelector("test")
+    SelectorToken::ClassSelector("test".into())
 );
 
 tokenize!(tokenize_35, "div.test.warn",
     SelectorToken::TypeSelector("div"),
-    SelectorToken::ClassSelector("test"),
-    SelectorToken::ClassSelector("warn")
+    SelectorToken::ClassSelector("test".into()),
+    SelectorToken::ClassSelector("warn".into())
 );
 
 tokenize!(tokenize_36, "div#id",
     SelectorToken::TypeSelector("div"),
-    SelectorToken::IdSelector("id")
+    SelectorToken::IdSelector("id".into())
 );
 
 tokenize!(tokenize_37, "*[id]",
     SelectorToken::UniversalSelector,
-    SelectorToken::AttributeSelector("id", AttributeOperator::Exists)
+    SelectorToken::AttributeSelector("id", AttributeOperator::Exists, false, false)
 );
 
 tokenize!(tokenize_38, "*.test",
     SelectorToken::UniversalSelector,
-    SelectorToken::ClassSelector("test")
+    SelectorToken::ClassSelector("test".into())
 );
 
 tokenize!(tokenize_39, "*#id",
     SelectorToken::UniversalSelector,
-    SelectorToken::IdSelector("id")
+    SelectorToken::IdSelector("id".into())
 );
 
 tokenize!(tokenize_40, "div * p",
@@ -220,13 +220,13 @@ tokenize!(tokenize_40, "div * p",
 
 tokenize!(tokenize_41, "div[id=test][color=red]",
     SelectorToken::TypeSelector("div"),
-    SelectorToken::AttributeSelector("id", AttributeOperator::Matches("test")),
-    SelectorToken::AttributeSelector("color", AttributeOperator::Matches("red"))
+    SelectorToken::AttributeSelector("id", AttributeOperator::Matches("test"), false, false),
+    SelectorToken::AttributeSelector("color", AttributeOperator::Matches("red"), false, false)
 );
 
 tokenize!(tokenize_42, "a.external:visited",
     SelectorToken::TypeSelector("a"),
-    SelectorToken::ClassSelector("external"),
+    SelectorToken::ClassSelector("external".into()),
     SelectorToken::PseudoClass("visited")
 );
 
@@ -241,11 +241,181 @@ tokenize!(tokenize_44, "a\nb",
 );
 
 tokenize!(tokenize_45, ".warn :first-child",
-    SelectorToken::ClassSelector("warn"),
+    SelectorToken::ClassSelector("warn".into()),
     SelectorToken::DescendantCombinator,
     SelectorToken::PseudoClass("first-child")
 );
 
+tokenize!(tokenize_46, ":nth-child(2n+1)",
+    SelectorToken::NthChildPseudoClass(2, 1)
+);
+
+tokenize!(tokenize_47, "li:nth-child( -n + 3 )",
+    SelectorToken::TypeSelector("li"),
+    SelectorToken::NthChildPseudoClass(-1, 3)
+);
+
+tokenize!(tokenize_48, ":nth-child(odd)",
+    SelectorToken::NthChildPseudoClass(2, 1)
+);
+
+tokenize!(tokenize_49, ":nth-child(even)",
+    SelectorToken::NthChildPseudoClass(2, 0)
+);
+
+tokenize!(tokenize_50, ":nth-child(5)",
+    SelectorToken::NthChildPseudoClass(0, 5)
+);
+
+tokenize!(tokenize_51, ":nth-child(n)",
+    SelectorToken::NthChildPseudoClass(1, 0)
+);
+
+tokenize!(tokenize_52, ":nth-child(-2n- 1)",
+    SelectorToken::NthChildPseudoClass(-2, -1)
+);
+
+tokenize!(tokenize_53, ".\\26 B",
+    SelectorToken::ClassSelector("&B".into())
+);
+
+tokenize!(tokenize_54, "#a\\\"b.c\\:d",
+    SelectorToken::IdSelector("a\"b".into()),
+    SelectorToken::ClassSelector("c:d".into())
+);
+
+tokenize!(tokenize_55, "\\31 0 > p",
+    SelectorToken::TypeSelector("\\31 0"),
+    SelectorToken::ChildCombinator,
+    SelectorToken::TypeSelector("p")
+);
+
+tokenize!(tokenize_56, "p:not(.warn)",
+    SelectorToken::TypeSelector("p"),
+    SelectorToken::NotPseudoClass(".warn")
+);
+
+tokenize!(tokenize_57, ":not( :nth-child(2n) ):not([title=')'])",
+    SelectorToken::NotPseudoClass(" :nth-child(2n) "),
+    SelectorToken::NotPseudoClass("[title=')']")
+);
+
+tokenize!(tokenize_58, "p::before",
+    SelectorToken::TypeSelector("p"),
+    SelectorToken::PseudoElement("before")
+);
+
+tokenize!(tokenize_59, "p:after",
+    SelectorToken::TypeSelector("p"),
+    SelectorToken::PseudoClass("after")
+);
+
+tokenize!(tokenize_60, "input[type=checkbox][type='checkbox'][type=\"checkbox\"]",
+    SelectorToken::TypeSelector("input"),
+    SelectorToken::AttributeSelector("type", AttributeOperator::Matches("checkbox"), false, false),
+    SelectorToken::AttributeSelector("type", AttributeOperator::Matches("checkbox"), true, false),
+    SelectorToken::AttributeSelector("type", AttributeOperator::Matches("checkbox"), true, false)
+);
+
+tokenize!(tokenize_61, "[ type = checkbox ][ lang |= 'en' ]",
+    SelectorToken::AttributeSelector("type", AttributeOperator::Matches("checkbox"), false, false),
+    SelectorToken::AttributeSelector("lang", AttributeOperator::StartsWith("en"), true, false)
+);
+
+tokenize!(tokenize_62, r".md\:flex.w-1\/2#a\:b:hover",
+    SelectorToken::ClassSelector("md:flex".into()),
+    SelectorToken::ClassSelector("w-1/2".into()),
+    SelectorToken::IdSelector("a:b".into()),
+    SelectorToken::PseudoClass("hover")
+);
+
+tokenize!(tokenize_63, "svg|rect > *|* |a svg|*.x",
+    SelectorToken::NamespacePrefix("svg"),
+    SelectorToken::TypeSelector("rect"),
+    SelectorToken::ChildCombinator,
+    SelectorToken::NamespacePrefix("*"),
+    SelectorToken::UniversalSelector,
+    SelectorToken::DescendantCombinator,
+    SelectorToken::NamespacePrefix(""),
+    SelectorToken::TypeSelector("a"),
+    SelectorToken::DescendantCombinator,
+    SelectorToken::NamespacePrefix("svg"),
+    SelectorToken::UniversalSelector,
+    SelectorToken::ClassSelector("x".into())
+);
+
+tokenize!(tokenize_64, "col || td col||*||svg|td *|*||a",
+    SelectorToken::TypeSelector("col"),
+    SelectorToken::ColumnCombinator,
+    SelectorToken::TypeSelector("td"),
+    SelectorToken::DescendantCombinator,
+    SelectorToken::TypeSelector("col"),
+    SelectorToken::ColumnCombinator,
+    SelectorToken::UniversalSelector,
+    SelectorToken::ColumnCombinator,
+    SelectorToken::NamespacePrefix("svg"),
+    SelectorToken::TypeSelector("td"),
+    SelectorToken::DescendantCombinator,
+    SelectorToken::NamespacePrefix("*"),
+    SelectorToken::UniversalSelector,
+    SelectorToken::ColumnCombinator,
+    SelectorToken::TypeSelector("a")
+);
+
+tokenize!(tokenize_65, "[lang|=en]||td",
+    SelectorToken::AttributeSelector("lang", AttributeOperator::StartsWith("en"), false, false),
+    SelectorToken::ColumnCombinator,
+    SelectorToken::TypeSelector("td")
+);
+
+tokenize!(tokenize_66, "[type='submit' i][lang|=EN I][a=b s][c = d  i ]",
+    SelectorToken::AttributeSelector("type", AttributeOperator::Matches("submit"), true, true),
+    SelectorToken::AttributeSelector("lang", AttributeOperator::StartsWith("EN"), false, true),
+    SelectorToken::AttributeSelector("a", AttributeOperator::Matches("b"), false, false),
+    SelectorToken::AttributeSelector("c", AttributeOperator::Matches("d"), false, true)
+);
+
+tokenize!(tokenize_67, "div/*c*/>h1>/*c*/p",
+    SelectorToken::TypeSelector("div"),
+    SelectorToken::ChildCombinator,
+    SelectorToken::TypeSelector("h1"),
+    SelectorToken::ChildCombinator,
+    SelectorToken::TypeSelector("p")
+);
+
+tokenize!(tokenize_68, "div/*c*/+h1+/*c*/p",
+    SelectorToken::TypeSelector("div"),
+    SelectorToken::AdjacentCombinator,
+    SelectorToken::TypeSelector("h1"),
+    SelectorToken::AdjacentCombinator,
+    SelectorToken::TypeSelector("p")
+);
+
+tokenize!(tokenize_69, "col/*c*/||td||/*c*/td",
+    SelectorToken::TypeSelector("col"),
+    SelectorToken::ColumnCombinator,
+    SelectorToken::TypeSelector("td"),
+    SelectorToken::ColumnCombinator,
+    SelectorToken::TypeSelector("td")
+);
+
+tokenize!(tokenize_70, "div/*c*/ /*d*/>/*e*/ h1.a/*f*/",
+    SelectorToken::TypeSelector("div"),
+    SelectorToken::ChildCombinator,
+    SelectorToken::TypeSelector("h1"),
+    SelectorToken::ClassSelector("a".into())
+);
+
+tokenize!(tokenize_71, "p:is(.a, div > p):where( [title=')'] )",
+    SelectorToken::TypeSelector("p"),
+    SelectorToken::IsPseudoClass(".a, div > p"),
+    SelectorToken::WherePseudoClass(" [title=')'] ")
+);
+
+tokenize!(tokenize_72, ":lang( zh-Hant-TW )",
+    SelectorToken::LangPseudoClass("zh-Hant-TW")
+);
+
 macro_rules! malformed {
     ($name:ident, $text:expr, $err_str:expr) => (
         #[test]
@@ -291,7 +461,7 @@ malformed!(malformed_12, ":lang()", "invalid language pseudo-class");
 
 malformed!(malformed_13, ":lang( )", "invalid language pseudo-class");
 
-malformed!(malformed_14, "::first-child", "invalid ident at 1:2");
+malformed!(malformed_14, ":::first-child", "invalid ident at 1:3");
 
 malformed!(malformed_15, "[olor:red", "invalid or unsupported attribute selector");
 
@@ -356,3 +526,229 @@ tokenize!(comment_12, "a /**//**/ b",
     SelectorToken::DescendantCombinator,
     SelectorToken::TypeSelector("b")
 );
+
+malformed!(malformed_19, ":nth-child()", "invalid nth-child pseudo-class");
+
+malformed!(malformed_20, ":nth-child(n+)", "invalid nth-child pseudo-class");
+
+malformed!(malformed_21, ":nth-child(2 n)", "invalid nth-child pseudo-class");
+
+malformed!(malformed_22, ":nth-child(first)", "invalid nth-child pseudo-class");
+
+malformed!(malformed_23, ":nth-child(2n+1", "unexpected end of stream");
+
+malformed!(malformed_24, ":not(p", "unexpected end of stream");
+
+malformed!(malformed_25, "a + ~ b", "unexpected combinator");
+
+malformed!(malformed_26, "[type=check box]", "invalid or unsupported attribute selector");
+
+malformed!(malformed_27, "[type=check;box]", "invalid or unsupported attribute selector");
+
+malformed!(malformed_28, "[type=5]", "invalid or unsupported attribute selector");
+
+malformed!(malformed_29, "[type=]", "invalid or unsupported attribute selector");
+
+malformed!(malformed_30, "svg|.x", "invalid ident at 1:5");
+
+malformed!(malformed_31, "svg|", "invalid ident at 1:5");
+
+malformed!(malformed_32, "a.b|c", "unexpected selector");
+
+malformed!(malformed_33, "|| td", "unexpected combinator");
+
+malformed!(malformed_34, "col || > td", "unexpected combinator");
+
+malformed!(malformed_35, "col ||", "selector missing");
+
+malformed!(malformed_36, "[type i]", "invalid or unsupported attribute selector");
+
+malformed!(malformed_37, "[type=submit x]", "invalid or unsupported attribute selector");
+
+malformed!(malformed_38, "[type=submit i i]", "invalid or unsupported attribute selector");
+
+malformed!(malformed_39, ":is(.a", "unexpected end of stream");
+
+malformed!(malformed_40, ":lang(en US)", "invalid language pseudo-class");
+
+malformed!(malformed_41, ":lang(-en)", "invalid language pseudo-class");
+
+malformed!(malformed_42, ":lang('en')", "invalid language pseudo-class");
+
+macro_rules! tokenize_with_comments {
+    ($name:ident, $text:expr, $( $token:expr ),*) => (
+        #[test]
+        fn $name() {
+            let mut t = SelectorTokenizer::new_with_comments($text);
+            $(
+                assert_eq!(t.next().unwrap().unwrap(), $token);
+            )*
+
+            assert!(t.next().is_none());
+        }
+    )
+}
+
+tokenize_with_comments!(with_comments_01, "/* comment */a",
+    SelectorToken::Comment(" comment "),
+    SelectorToken::TypeSelector("a")
+);
+
+tokenize_with_comments!(with_comments_02, "a /**/ /* *\\/*/b",
+    SelectorToken::TypeSelector("a"),
+    SelectorToken::DescendantCombinator,
+    SelectorToken::Comment(""),
+    SelectorToken::Comment(" *\\/"),
+    SelectorToken::TypeSelector("b")
+);
+
+tokenize_with_comments!(with_comments_03, "a/*1*/ > /*2*/b /*3*/",
+    SelectorToken::TypeSelector("a"),
+    SelectorToken::Comment("1"),
+    SelectorToken::ChildCombinator,
+    SelectorToken::Comment("2"),
+    SelectorToken::TypeSelector("b"),
+    SelectorToken::Comment("3")
+);
+
+#[test]
+fn with_comments_04() {
+    // Like in the default mode, a comment is not a combinator.
+    let mut t = SelectorTokenizer::new_with_comments("a/**/b");
+    assert_eq!(t.next().unwrap().unwrap(), SelectorToken::TypeSelector("a"));
+    assert_eq!(t.next().unwrap().unwrap(), SelectorToken::Comment(""));
+    assert_eq!(t.next().unwrap(), Err(Error::UnexpectedSelector));
+    assert!(SelectorTokenizer::from("a/**/b").any(|t| t == Err(Error::UnexpectedSelector)));
+}
+
+tokenize_with_comments!(with_comments_05, "a/*1*/+/*2*/b",
+    SelectorToken::TypeSelector("a"),
+    SelectorToken::Comment("1"),
+    SelectorToken::AdjacentCombinator,
+    SelectorToken::Comment("2"),
+    SelectorToken::TypeSelector("b")
+);
+
+#[test]
+fn peek_01() {
+    let mut t = SelectorTokenizer::from("a > b");
+    assert_eq!(t.peek().unwrap().unwrap(), SelectorToken::TypeSelector("a"));
+    assert_eq!(t.peek().unwrap().unwrap(), SelectorToken::TypeSelector("a"));
+    assert_eq!(t.next().unwrap().unwrap(), SelectorToken::TypeSelector("a"));
+    assert_eq!(t.peek().unwrap().unwrap(), SelectorToken::ChildCombinator);
+    assert_eq!(t.next().unwrap().unwrap(), SelectorToken::ChildCombinator);
+    assert_eq!(t.next().unwrap().unwrap(), SelectorToken::TypeSelector("b"));
+    assert!(t.peek().is_none());
+    assert!(t.next().is_none());
+}
+
+#[test]
+fn peek_02() {
+    // An error is reported by both, but only `next` stops the tokenizer.
+    let mut t = SelectorTokenizer::from("a > > b");
+    t.next();
+    t.next();
+    assert_eq!(t.peek().unwrap(), Err(Error::UnexpectedCombinator));
+    assert_eq!(t.next().unwrap(), Err(Error::UnexpectedCombinator));
+    assert!(t.peek().is_none());
+}
+
+#[test]
+fn iterator_01() {
+    let mut t = SelectorTokenizer::from("a > > b");
+    let tokens: Vec<_> = t.by_ref().take_while(|t| t.is_ok()).collect();
+    assert_eq!(tokens, vec![Ok(SelectorToken::TypeSelector("a")), Ok(SelectorToken::ChildCombinator)]);
+
+    // Stays finished after an error.
+    assert!(t.next().is_none());
+    assert!(t.next().is_none());
+}
+
+#[test]
+fn iterator_02() {
+    let mut t = SelectorTokenizer::from("a >");
+    assert_eq!(t.next().unwrap().unwrap(), SelectorToken::TypeSelector("a"));
+    assert_eq!(t.next().unwrap().unwrap(), SelectorToken::ChildCombinator);
+    assert_eq!(t.next().unwrap(), Err(Error::SelectorMissing));
+    assert!(t.next().is_none());
+    assert!(t.next().is_none());
+}
+
+#[test]
+fn pos_01() {
+    let mut t = SelectorTokenizer::from("a b");
+    t.next();
+    let saved = t.clone();
+    assert_eq!(t.next().unwrap().unwrap(), SelectorToken::DescendantCombinator);
+    assert_eq!(t.next().unwrap().unwrap(), SelectorToken::TypeSelector("b"));
+    assert_eq!(t.pos(), 3);
+
+    // The restored tokenizer is still after a selector, so a combinator is expected.
+    t = saved;
+    assert_eq!(t.pos(), 1);
+    assert_eq!(t.next().unwrap().unwrap(), SelectorToken::DescendantCombinator);
+}
+
+#[test]
+fn hash_01() {
+    use std::collections::HashSet;
+
+    let tokens: HashSet<_> = SelectorTokenizer::from("a > b > a.c").map(|t| t.unwrap()).collect();
+    assert_eq!(tokens.len(), 4);
+    assert!(tokens.contains(&SelectorToken::ChildCombinator));
+
+    let combinators: HashSet<_> = Selector::parse("a > b > c d").unwrap()
+        .components().filter_map(|(_, c)| c).collect();
+    assert_eq!(combinators.len(), 2);
+    assert!(combinators.contains(&Combinator::Descendant));
+}
+
+#[test]
+fn escaped_01() {
+    use std::borrow::Cow;
+
+    let mut t = SelectorTokenizer::from(r".a.md\:flex#b\/c");
+    assert!(matches!(t.next().unwrap().unwrap(), SelectorToken::ClassSelector(Cow::Borrowed("a"))));
+    assert!(matches!(t.next().unwrap().unwrap(), SelectorToken::ClassSelector(Cow::Owned(ref v)) if v == "md:flex"));
+    assert!(matches!(t.next().unwrap().unwrap(), SelectorToken::IdSelector(Cow::Owned(ref v)) if v == "b/c"));
+
+    // Parsed selectors still keep the escapes.
+    let selector = Selector::parse(r".md\:flex#b\/c").unwrap();
+    assert_eq!(selector.to_string(), r"*[class~='md\:flex']#b\/c");
+}
+
+#[test]
+fn span_01() {
+    let text = "a /* c */ [href] >p:not(b), c";
+    let spans: Vec<_> = std::iter::from_fn({
+        let mut t = SelectorTokenizer::from(text);
+        move || t.next_spanned()
+    }).map(|v| {
+        let (token, span) = v.unwrap();
+        (token, &text[span])
+    }).collect();
+
+    assert_eq!(spans, vec![
+        (SelectorToken::TypeSelector("a"), "a"),
+        (SelectorToken::DescendantCombinator, " /* c */ "),
+        (SelectorToken::AttributeSelector("href", AttributeOperator::Exists, false, false), "[href]"),
+        (SelectorToken::ChildCombinator, ">"),
+        (SelectorToken::TypeSelector("p"), "p"),
+        (SelectorToken::NotPseudoClass("b"), ":not(b)"),
+    ]);
+}
+
+#[test]
+fn span_02() {
+    let mut t = SelectorTokenizer::new_with_comments("a /* c */b");
+    assert_eq!(t.next_spanned().unwrap().unwrap(), (SelectorToken::TypeSelector("a"), 0..1));
+    assert_eq!(t.next_spanned().unwrap().unwrap(), (SelectorToken::DescendantCombinator, 1..2));
+    assert_eq!(t.next_spanned().unwrap().unwrap(), (SelectorToken::Comment(" c "), 2..9));
+    assert_eq!(t.next_spanned().unwrap().unwrap(), (SelectorToken::TypeSelector("b"), 9..10));
+    assert!(t.next_spanned().is_none());
+
+    let mut t = SelectorTokenizer::from("a > > b");
+    t.next_spanned();
+    t.next_spanned();
+    assert_eq!(t.next_spanned().unwrap(), Err(Error::UnexpectedCombinator));
+}
@@ -0,0 +1,38 @@
+#![cfg(feature = "serde")]
+
+use simplecss::*;
+
+#[test]
+fn stylesheet_round_trip() {
+    let style = StyleSheet::parse("\
+        div > p:first-child, a[href|=en] { color: red !important; margin: 0 }
+        .warn + *:lang(en) { --main-color: #fff }
+        li:nth-child(2n+1) { width: 5px }
+    ");
+
+    let json = serde_json::to_string(&style).unwrap();
+    let style2: StyleSheet = serde_json::from_str(&json).unwrap();
+    assert_eq!(style.to_string(), style2.to_string());
+}
+
+#[test]
+fn declaration_borrows() {
+    let json = r#"{"name":"color","value":"red","important":true,"raw_value":" red "}"#;
+    let d: Declaration = serde_json::from_str(json).unwrap();
+    assert_eq!(d, Declaration::new("color", "red", true));
+    assert_eq!(d.raw_value, " red ");
+    assert_eq!(serde_json::to_string(&d).unwrap(), json);
+
+    let d = Declaration::parse_single("color: red /**/").unwrap();
+    let json = serde_json::to_string(&d).unwrap();
+    let d2: Declaration = serde_json::from_str(&json).unwrap();
+    assert_eq!(d2.raw_value, " red /**/");
+}
+
+#[test]
+fn selector_list_round_trip() {
+    let list = SelectorList::parse("h1, div p, [id=test]").unwrap();
+    let json = serde_json::to_string(&list).unwrap();
+    let list2: SelectorList = serde_json::from_str(&json).unwrap();
+    assert_eq!(list.to_string(), list2.to_string());
+}
@@ -0,0 +1,48 @@
+use simplecss::*;
+
+fn expand(name: &str, value: &str) -> Option<Vec<(String, String)>> {
+    expand_shorthand(&Declaration::new(name, value, false))
+}
+
+fn sides(name: &str, suffix: &str, values: [&str; 4]) -> Option<Vec<(String, String)>> {
+    Some(["top", "right", "bottom", "left"].iter().zip(values.iter())
+        .map(|(side, value)| (format!("{}-{}{}", name, side, suffix), value.to_string()))
+        .collect())
+}
+
+#[test]
+fn values_count() {
+    assert_eq!(expand("margin", "1px"), sides("margin", "", ["1px", "1px", "1px", "1px"]));
+    assert_eq!(expand("margin", "1px 2px"), sides("margin", "", ["1px", "2px", "1px", "2px"]));
+    assert_eq!(expand("margin", "1px 2px 3px"), sides("margin", "", ["1px", "2px", "3px", "2px"]));
+    assert_eq!(expand("margin", "1px 2px 3px 4px"), sides("margin", "", ["1px", "2px", "3px", "4px"]));
+}
+
+#[test]
+fn properties() {
+    assert_eq!(expand("padding", "0 auto"), sides("padding", "", ["0", "auto", "0", "auto"]));
+    assert_eq!(expand("border-width", "thin 2px"), sides("border", "-width", ["thin", "2px", "thin", "2px"]));
+    assert_eq!(expand("border-style", "solid"), sides("border", "-style", ["solid", "solid", "solid", "solid"]));
+    assert_eq!(expand("border-color", "red rgb(0, 0, 0) #fff"),
+               sides("border", "-color", ["red", "rgb(0, 0, 0)", "#fff", "rgb(0, 0, 0)"]));
+    assert_eq!(expand("MARGIN", "1px"), sides("margin", "", ["1px", "1px", "1px", "1px"]));
+}
+
+#[test]
+fn complex_values() {
+    assert_eq!(expand("margin", "calc(1px + 2%) /* c */ -5px"),
+               sides("margin", "", ["calc(1px + 2%)", "-5px", "calc(1px + 2%)", "-5px"]));
+    assert_eq!(expand("padding", "inherit"), sides("padding", "", ["inherit", "inherit", "inherit", "inherit"]));
+}
+
+#[test]
+fn not_expanded() {
+    assert_eq!(expand("color", "red"), None);
+    assert_eq!(expand("border", "1px solid red"), None);
+    assert_eq!(expand("margin-top", "1px"), None);
+    assert_eq!(expand("margin", ""), None);
+    assert_eq!(expand("margin", "1px 2px 3px 4px 5px"), None);
+    assert_eq!(expand("margin", "1px, 2px"), None);
+    assert_eq!(expand("margin", "var(--a) 2px"), None);
+    assert_eq!(expand("margin", "1px {"), None);
+}
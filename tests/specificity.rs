@@ -3,47 +3,108 @@ use simplecss::*;
 #[test]
 fn spec_01() {
     let selectors = Selector::parse("*").unwrap();
-    assert_eq!(selectors.specificity(), [0, 0, 0]);
+    assert_eq!(selectors.specificity(), Specificity([0, 0, 0]));
 }
 
 #[test]
 fn spec_02() {
     let selectors = Selector::parse("li").unwrap();
-    assert_eq!(selectors.specificity(), [0, 0, 1]);
+    assert_eq!(selectors.specificity(), Specificity([0, 0, 1]));
 }
 
 #[test]
 fn spec_03() {
     let selectors = Selector::parse("ul li").unwrap();
-    assert_eq!(selectors.specificity(), [0, 0, 2]);
+    assert_eq!(selectors.specificity(), Specificity([0, 0, 2]));
 }
 
 #[test]
 fn spec_04() {
     let selectors = Selector::parse("ul ol + li").unwrap();
-    assert_eq!(selectors.specificity(), [0, 0, 3]);
+    assert_eq!(selectors.specificity(), Specificity([0, 0, 3]));
 }
 
 #[test]
 fn spec_05() {
     let selectors = Selector::parse("h1 + *[rel=up]").unwrap();
-    assert_eq!(selectors.specificity(), [0, 1, 1]);
+    assert_eq!(selectors.specificity(), Specificity([0, 1, 1]));
 }
 
 #[test]
 fn spec_06() {
     let selectors = Selector::parse("ul ol li.red").unwrap();
-    assert_eq!(selectors.specificity(), [0, 1, 3]);
+    assert_eq!(selectors.specificity(), Specificity([0, 1, 3]));
 }
 
 #[test]
 fn spec_07() {
     let selectors = Selector::parse("li.red.level").unwrap();
-    assert_eq!(selectors.specificity(), [0, 2, 1]);
+    assert_eq!(selectors.specificity(), Specificity([0, 2, 1]));
 }
 
 #[test]
 fn spec_08() {
     let selectors = Selector::parse("#x34y").unwrap();
-    assert_eq!(selectors.specificity(), [1, 0, 0]);
+    assert_eq!(selectors.specificity(), Specificity([1, 0, 0]));
+}
+
+#[test]
+fn spec_09() {
+    let selectors = Selector::parse("p:not(#x)").unwrap();
+    assert_eq!(selectors.specificity(), Specificity([1, 0, 1]));
+}
+
+#[test]
+fn spec_10() {
+    let selectors = Selector::parse("*:not(p):not(.a)").unwrap();
+    assert_eq!(selectors.specificity(), Specificity([0, 1, 1]));
+}
+
+#[test]
+fn spec_11() {
+    let selectors = Selector::parse("p.a::before").unwrap();
+    assert_eq!(selectors.specificity(), Specificity([0, 1, 2]));
+}
+
+#[test]
+fn spec_12() {
+    // The most specific argument.
+    let selectors = Selector::parse("p:is(.a, #b, div)").unwrap();
+    assert_eq!(selectors.specificity(), Specificity([1, 0, 1]));
+}
+
+#[test]
+fn spec_13() {
+    let selectors = Selector::parse("p:where(.a, #b, div)").unwrap();
+    assert_eq!(selectors.specificity(), Specificity([0, 0, 1]));
+}
+
+#[test]
+fn spec_14() {
+    assert_eq!(Selector::parse("[id=a]").unwrap().specificity(), Specificity([0, 1, 0]));
+    assert_eq!(Selector::parse("[id]").unwrap().specificity(), Specificity([0, 1, 0]));
+    assert_eq!(Selector::parse("p#a[id=a]").unwrap().specificity(), Specificity([1, 1, 1]));
+}
+
+#[test]
+fn spec_ord() {
+    let a = Selector::parse("#a").unwrap().specificity();
+    let b = Selector::parse("div.a.b.c p span").unwrap().specificity();
+    assert!(a > b);
+    assert!(b > Selector::parse("div.a.b.c p").unwrap().specificity());
+    assert_eq!(Selector::parse("*").unwrap().specificity(), Specificity::default());
+}
+
+#[test]
+fn spec_to_string() {
+    let selectors = Selector::parse("#a > p.b:first-child").unwrap();
+    assert_eq!(selectors.specificity().to_string(), "1,2,1");
+}
+
+#[test]
+fn spec_counts() {
+    let spec = Selector::parse("#a > p.b:first-child::before").unwrap().specificity();
+    assert_eq!(spec.id_count(), 1);
+    assert_eq!(spec.class_count(), 2);
+    assert_eq!(spec.type_count(), 2);
 }
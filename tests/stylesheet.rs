@@ -63,7 +63,7 @@ fn style_10() {
 #[test]
 fn style_11() {
     let style = StyleSheet::parse("@import \"subs.css\";\na { color:red }");
-    assert_eq!(style.to_string(), "a { color:red; }");
+    assert_eq!(style.to_string(), "@import \"subs.css\";\na { color:red; }");
 }
 
 #[test]
@@ -91,7 +91,7 @@ fn style_14() {
 #[test]
 fn style_15() {
     let style = StyleSheet::parse("p { color; color:green }");
-    assert_eq!(style.to_string(), ""); // TODO: should be 'p { color:green; }'
+    assert_eq!(style.to_string(), "p { color:green; }");
 }
 
 #[test]
@@ -103,7 +103,7 @@ fn style_16() {
 #[test]
 fn style_17() {
     let style = StyleSheet::parse("p { color:green; color:; color:red; }");
-    assert_eq!(style.to_string(), "p { color:green; }");
+    assert_eq!(style.to_string(), "p { color:green;color:red; }");
 }
 
 #[test]
@@ -115,7 +115,8 @@ fn style_18() {
 #[test]
 fn style_19() {
     let style = StyleSheet::parse("p { color{;color:maroon} color:green; }");
-    assert_eq!(style.to_string(), ""); // TODO: should be 'p { color:green; }'
+    // A block is a part of the invalid declaration, which ends only at `;`.
+    assert_eq!(style.to_string(), "");
 }
 
 #[test]
@@ -133,3 +134,966 @@ fn style_21() {
     let style = StyleSheet::parse(":le>*");
     assert_eq!(style.to_string(), "");
 }
+
+#[test]
+fn style_22() {
+    let style = StyleSheet::parse("p { color: red");
+    assert_eq!(style.to_string(), "p { color:red; }");
+}
+
+#[test]
+fn style_23() {
+    let style = StyleSheet::parse("a { color: green } p { color: red; background: blue ");
+    assert_eq!(style.to_string(), "a { color:green; }\np { color:red;background:blue; }");
+}
+
+#[test]
+fn style_24() {
+    let style = StyleSheet::parse("p { color: red !important");
+    assert_eq!(style.to_string(), "p { color:red !important; }");
+}
+
+#[test]
+fn style_25() {
+    let style = StyleSheet::parse("html { --main-color: #fff } p { color: var(--main-color, blue) }");
+    assert_eq!(style.to_string(), "html { --main-color:#fff; }\np { color:var(--main-color, blue); }");
+}
+
+#[test]
+fn style_26() {
+    let style = StyleSheet::parse("p { 123: red; color: green }");
+    assert_eq!(style.to_string(), "p { 123:red;color:green; }");
+}
+
+#[test]
+fn style_27() {
+    let options = ParseOptions { validate_property_names: true, ..ParseOptions::default() };
+    let style = StyleSheet::parse_with_options("p { 123: red; color: green; -1a: red; --x: 1 }", options);
+    assert_eq!(style.to_string(), "p { color:green;--x:1; }");
+}
+
+#[test]
+fn style_28() {
+    let style = StyleSheet::parse("p { color: ; ; width: 5px }");
+    assert_eq!(style.to_string(), "p { width:5px; }");
+}
+
+#[test]
+fn style_29() {
+    // Strings and blocks inside an invalid declaration are skipped as a whole.
+    let style = StyleSheet::parse("p { a ~ 'x;}'; b ~ {;}; c ~ \"}\"; color: green; d ~ } a { e ~ }");
+    assert_eq!(style.to_string(), "p { color:green; }");
+}
+
+#[test]
+fn style_30() {
+    // An unclosed comment after a complete declaration doesn't discard it.
+    let (style, diagnostics) = StyleSheet::parse_with_diagnostics("p { color: red; /* unclosed");
+    assert_eq!(style.to_string(), "p { color:red; }");
+    assert_eq!(diagnostics, vec![
+        (TextPos::new(1, 16, 15), Error::InvalidComment(TextPos::new(1, 17, 16))),
+    ]);
+
+    let style = StyleSheet::parse("p { color: red;; /**/ ; width: 5px; /* unclosed");
+    assert_eq!(style.to_string(), "p { color:red;width:5px; }");
+}
+
+#[test]
+fn sort_grouped() {
+    // Each selector in a group is sorted by its own specificity.
+    let style = StyleSheet::parse("#b, a, .c { color:red } p { color:blue }");
+    let selectors: Vec<_> = style.rules.iter().map(|r| r.selector.to_string()).collect();
+    assert_eq!(selectors, vec!["a", "p", "*[class~='c']", "*#b"]);
+    assert_eq!(style.rules[3].declarations, style.rules[0].declarations);
+    assert_eq!(style.rules[1].declarations[0].value, "blue");
+}
+
+#[test]
+fn diagnostics_01() {
+    let (style, diagnostics) = StyleSheet::parse_with_diagnostics("a { color:red }");
+    assert_eq!(style.to_string(), "a { color:red; }");
+    assert!(diagnostics.is_empty());
+}
+
+#[test]
+fn diagnostics_02() {
+    let (style, diagnostics) = StyleSheet::parse_with_diagnostics("\
+@page { margin: 1cm }
+a:hover, b:unknown { color:red }
+h2 & h3 { color: red }
+p { color; color:green }
+");
+    assert_eq!(style.to_string(), "p { color:green; }\na:hover { color:red; }");
+    assert_eq!(diagnostics, vec![
+        (TextPos::new(1, 1, 0), Error::UnsupportedAtRule),
+        (TextPos::new(2, 10, 31), Error::UnsupportedPseudoClass),
+        (TextPos::new(3, 1, 55), Error::InvalidIdent(TextPos::new(1, 4, 3))),
+        (TextPos::new(4, 5, 82), Error::InvalidByte { expected: b':', actual: b';', pos: TextPos::new(4, 10, 87) }),
+    ]);
+}
+
+#[test]
+fn diagnostics_missing_block() {
+    // A selector without a declaration block is reported, but an unclosed block is not.
+    let (style, diagnostics) = StyleSheet::parse_with_diagnostics("a { color:red } div");
+    assert_eq!(style.to_string(), "a { color:red; }");
+    assert_eq!(diagnostics, vec![(TextPos::new(1, 17, 16), Error::UnexpectedEndOfStream)]);
+
+    let (_, diagnostics) = StyleSheet::parse_with_diagnostics("div, p");
+    assert_eq!(diagnostics, vec![(TextPos::new(1, 1, 0), Error::UnexpectedEndOfStream)]);
+
+    let (style, diagnostics) = StyleSheet::parse_with_diagnostics("div { color:red");
+    assert_eq!(style.to_string(), "div { color:red; }");
+    assert!(diagnostics.is_empty());
+}
+
+#[test]
+fn diagnostics_selector_at_end() {
+    // An invalid selector at the end of the text is reported only once.
+    let (style, diagnostics) = StyleSheet::parse_with_diagnostics("a { color:red } div >");
+    assert_eq!(style.to_string(), "a { color:red; }");
+    assert_eq!(diagnostics, vec![(TextPos::new(1, 17, 16), Error::SelectorMissing)]);
+}
+
+#[test]
+fn strict() {
+    assert!(StyleSheet::try_parse("a { color:red } b { color:green }").is_ok());
+    assert_eq!(StyleSheet::try_parse("a { color:red } div").unwrap_err(),
+               (TextPos::new(1, 17, 16), Error::UnexpectedEndOfStream));
+    assert_eq!(StyleSheet::try_parse("a { color:red } div { color:red").unwrap_err(),
+               (TextPos::new(1, 17, 16), Error::UnexpectedEndOfStream));
+    assert_eq!(StyleSheet::try_parse("a { color } b { color:green }").unwrap_err(),
+               (TextPos::new(1, 5, 4), Error::InvalidByte { expected: b':', actual: b'}', pos: TextPos::new(1, 11, 10) }));
+
+    // Parsing stops at the first statement with an error.
+    let options = ParseOptions { strict: true, ..ParseOptions::default() };
+    let style = StyleSheet::parse_with_options("a { color:red } b:unknown { color:red } c { color:red }", options);
+    assert_eq!(style.to_string(), "a { color:red; }");
+}
+
+#[test]
+fn diagnostics_recoverable() {
+    let (_, diagnostics) = StyleSheet::parse_with_diagnostics("\
+        b:unknown { color:green }
+        a { color:red; color; color:green }
+        div /* { color:red }
+    ");
+    let errors: Vec<_> = diagnostics.iter().map(|(_, e)| e.recoverable()).collect();
+    assert_eq!(errors, vec![true, true, false]);
+    assert!(!Error::UnexpectedEndOfStream.recoverable());
+}
+
+#[test]
+fn diagnostics_dangling_combinator() {
+    for (text, error) in &[
+        ("a { color:red }\ndiv > > p { color:blue }\nb { color:green }", Error::UnexpectedCombinator),
+        ("a { color:red }\ndiv > { color:blue }\nb { color:green }", Error::SelectorMissing),
+        ("a { color:red }\ndiv + { color:blue }\nb { color:green }", Error::SelectorMissing),
+        ("a { color:red }\n> div { color:blue }\nb { color:green }", Error::UnexpectedCombinator),
+    ] {
+        let (style, diagnostics) = StyleSheet::parse_with_diagnostics(text);
+        assert_eq!(style.to_string(), "a { color:red; }\nb { color:green; }");
+        assert_eq!(diagnostics, vec![(TextPos::new(2, 1, 16), *error)]);
+    }
+}
+
+#[test]
+fn diagnostics_line_of() {
+    let text = "a { color:red }\n\nb > > c { color:red }\nd { color:red }";
+    let (_, diagnostics) = StyleSheet::parse_with_diagnostics(text);
+    assert_eq!(diagnostics[0].0.row, 3);
+    assert_eq!(diagnostics[0].0.line_of(text), "b > > c { color:red }");
+    assert_eq!(TextPos::line_count(text), 4);
+
+    assert_eq!(TextPos::new(2, 1, 16).line_of(text), "");
+    assert_eq!(TextPos::new(1, 1, 0).line_of(text), "a { color:red }");
+    assert_eq!(TextPos::new(4, 16, text.len()).line_of(text), "d { color:red }");
+    assert_eq!(TextPos::new(1, 1, 0).line_of(""), "");
+}
+
+#[test]
+fn diagnostics_offset() {
+    let text = "a { color:red }\nb:unknown { color:green }";
+    let (_, diagnostics) = StyleSheet::parse_with_diagnostics(text);
+    assert_eq!(diagnostics, vec![(TextPos::new(2, 1, 16), Error::UnsupportedPseudoClass)]);
+    assert!(text[diagnostics[0].0.offset..].starts_with("b:unknown"));
+}
+
+#[test]
+fn diagnostics_multibyte() {
+    // Columns are counted in characters and not in bytes.
+    let (_, diagnostics) = StyleSheet::parse_with_diagnostics("/* 日本語 */ p { color; }");
+    assert_eq!(diagnostics, vec![
+        (TextPos::new(1, 15, 20), Error::InvalidByte { expected: b':', actual: b';', pos: TextPos::new(1, 20, 25) }),
+    ]);
+}
+
+#[test]
+fn diagnostics_03() {
+    let (style, diagnostics) = StyleSheet::parse_with_diagnostics("a { color:red } /* unclosed");
+    assert_eq!(style.to_string(), "a { color:red; }");
+    assert_eq!(diagnostics, vec![(TextPos::new(1, 17, 16), Error::InvalidComment(TextPos::new(1, 17, 16)))]);
+}
+
+#[test]
+fn diagnostics_04() {
+    let (style, diagnostics) = StyleSheet::parse_with_diagnostics("a:not(b > c) { color:red } a:not(b) { color:green }");
+    assert_eq!(style.to_string(), "a:not(b) { color:green; }");
+    assert_eq!(diagnostics, vec![(TextPos::new(1, 1, 0), Error::UnexpectedSelector)]);
+}
+
+#[test]
+fn parse_bound_01() {
+    let text = "<style>a { color:red }</style><p>b { color:green }</p>";
+    let start = text.find('>').unwrap() + 1;
+    let end = text.find("</style>").unwrap();
+    let style = StyleSheet::parse_bound(text, start, end);
+    assert_eq!(style.to_string(), "a { color:red; }");
+}
+
+#[test]
+fn parse_bound_02() {
+    // Parsing must stop at the end of the range even inside a rule.
+    let text = "a { color:red } b { color:green } c { color:blue }";
+    let style = StyleSheet::parse_bound(text, 16, 33);
+    assert_eq!(style.to_string(), "b { color:green; }");
+
+    let style = StyleSheet::parse_bound(text, 16, 28);
+    assert_eq!(style.to_string(), "b { color:gr; }");
+}
+
+#[test]
+fn parse_bound_03() {
+    let text = "<style>\na { color:red }\n  > b { color:green }\n</style>";
+    let start = text.find('>').unwrap() + 1;
+    let end = text.find("</style>").unwrap();
+    let (style, diagnostics) = StyleSheet::parse_bound_with_diagnostics(text, start, end);
+    assert_eq!(style.to_string(), "a { color:red; }");
+    assert_eq!(diagnostics, vec![(TextPos::new(3, 3, 26), Error::UnexpectedCombinator)]);
+}
+
+#[test]
+fn parse_bound_charset() {
+    let text = "<style>@charset \"utf-8\"; a{color:red}</style>";
+    let (style, diagnostics) = StyleSheet::parse_bound_with_diagnostics(text, 7, text.len() - 8);
+    assert_eq!(style.charset, Some("utf-8"));
+    assert_eq!(style.to_string(), "@charset \"utf-8\";\na { color:red; }");
+    assert!(diagnostics.is_empty());
+
+    // Still must be the first one.
+    let text = "<style>a{color:red} @charset \"utf-8\";</style>";
+    let (style, diagnostics) = StyleSheet::parse_bound_with_diagnostics(text, 7, text.len() - 8);
+    assert_eq!(style.charset, None);
+    assert_eq!(diagnostics, vec![(TextPos::new(1, 21, 20), Error::UnsupportedAtRule)]);
+}
+
+#[test]
+fn minified_01() {
+    let style = StyleSheet::parse("");
+    assert_eq!(style.to_minified_string(), "");
+}
+
+#[test]
+fn minified_02() {
+    let style = StyleSheet::parse("\
+        a > b { color: red !important; width: 5px; }
+        p + q { margin : 0 }
+    ");
+    assert_eq!(style.to_minified_string(), "a>b{color:red!important;width:5px}p+q{margin:0}");
+}
+
+#[test]
+fn minified_03() {
+    let style = StyleSheet::parse("a, b { color: red } a { color: red } b { color: green }");
+    assert_eq!(style.to_minified_string(), "a,b{color:red}b{color:green}");
+}
+
+#[test]
+fn minified_04() {
+    let style = StyleSheet::parse("div p { content: 'a b' }");
+    assert_eq!(style.to_minified_string(), "div p{content:'a b'}");
+}
+
+#[test]
+fn minified_at_rules() {
+    let text = "@charset \"UTF-8\";@import \"a.css\" screen;@namespace svg \"http://www.w3.org/2000/svg\";\
+                @property --a{syntax:\"<length>\";inherits:false;initial-value:0}\
+                @font-face{font-family:A;src:url(a.woff)}\
+                @keyframes k{from{opacity:0}50%{opacity:.5}}\
+                p{color:red}";
+    let style = StyleSheet::parse(text);
+    assert_eq!(style.to_minified_string(), text);
+    assert_eq!(StyleSheet::parse(&style.to_string()).to_minified_string(), text);
+}
+
+#[test]
+fn pretty_01() {
+    let style = StyleSheet::parse("");
+    let mut out = String::new();
+    style.write_pretty(&mut out, 4).unwrap();
+    assert_eq!(out, "");
+}
+
+#[test]
+fn pretty_02() {
+    let style = StyleSheet::parse("\
+        a > b { color: red !important; width: 5px; }
+        @supports (display: grid) { p { display: grid } }
+    ");
+    let mut out = String::new();
+    style.write_pretty(&mut out, 4).unwrap();
+    assert_eq!(out, "\
+@supports (display: grid) {
+    p {
+        display: grid;
+    }
+}
+
+a > b {
+    color: red !important;
+    width: 5px;
+}
+");
+
+    let mut out = String::new();
+    style.write_pretty(&mut out, 0).unwrap();
+    assert!(out.ends_with("a > b {\ncolor: red !important;\nwidth: 5px;\n}\n"));
+}
+
+#[test]
+fn pretty_at_rules() {
+    let style = StyleSheet::parse("\
+        @charset 'UTF-8';
+        @import url(a.css);
+        @namespace 'x\"y';
+        @font-face { font-family: A }
+        @keyframes k { from, to { opacity: 0 } }
+        p { color: red }
+    ");
+    let mut out = String::new();
+    style.write_pretty(&mut out, 2).unwrap();
+    assert_eq!(out, "\
+@charset \"UTF-8\";
+@import \"a.css\";
+@namespace 'x\"y';
+
+@font-face {
+  font-family: A;
+}
+
+@keyframes k {
+  from {
+    opacity: 0;
+  }
+  to {
+    opacity: 0;
+  }
+}
+
+p {
+  color: red;
+}
+");
+
+    assert_eq!(StyleSheet::parse(&out).to_string(), style.to_string());
+}
+
+#[test]
+fn display_at_rules() {
+    let style = StyleSheet::parse("\
+        @import 'a.css';
+        @font-face { font-family: A }
+        @keyframes k { 12.5% { opacity: 0 } }
+    ");
+    assert_eq!(style.to_string(), "\
+@import \"a.css\";
+@font-face { font-family:A; }
+@keyframes k { 12.5% { opacity:0; } }");
+    assert_eq!(StyleSheet::parse(&style.to_string()).to_string(), style.to_string());
+}
+
+#[test]
+fn import_01() {
+    let style = StyleSheet::parse("\
+        @import 'a.css';
+        @import \"b.css\" screen;
+        @import url(c.css);
+        @import url( 'd.css' ) screen, print ;
+        @import url(\"e.css\")print;
+        a { color:red }
+    ");
+    assert_eq!(style.imports, vec![
+        Import { href: "a.css", media: None },
+        Import { href: "b.css", media: Some("screen") },
+        Import { href: "c.css", media: None },
+        Import { href: "d.css", media: Some("screen, print") },
+        Import { href: "e.css", media: Some("print") },
+    ]);
+    assert_eq!(style.to_string(), "@import \"a.css\";
+@import \"b.css\" screen;
+@import \"c.css\";
+@import \"d.css\" screen, print;
+@import \"e.css\" print;
+a { color:red; }");
+}
+
+#[test]
+fn import_02() {
+    let style = StyleSheet::parse("@import 'a.css'");
+    assert_eq!(style.imports, vec![Import { href: "a.css", media: None }]);
+}
+
+#[test]
+fn import_03() {
+    let (style, diagnostics) = StyleSheet::parse_with_diagnostics("@import a.css; a { color:red }");
+    assert!(style.imports.is_empty());
+    assert_eq!(style.to_string(), "a { color:red; }");
+    assert_eq!(diagnostics, vec![(TextPos::new(1, 1, 0), Error::InvalidValue(TextPos::new(1, 9, 8)))]);
+}
+
+#[test]
+fn namespace_01() {
+    let style = StyleSheet::parse("\
+        @namespace url(http://www.w3.org/1999/xhtml);
+        @namespace svg url( 'http://www.w3.org/2000/svg' ) ;
+        @namespace xlink \"http://www.w3.org/1999/xlink\";
+        @namespace svg 'svg2'
+    ");
+    assert_eq!(style.namespaces, vec![
+        Namespace { prefix: None, uri: "http://www.w3.org/1999/xhtml" },
+        Namespace { prefix: Some("svg"), uri: "http://www.w3.org/2000/svg" },
+        Namespace { prefix: Some("xlink"), uri: "http://www.w3.org/1999/xlink" },
+        Namespace { prefix: Some("svg"), uri: "svg2" },
+    ]);
+    assert_eq!(style.namespace_uri(None), Some("http://www.w3.org/1999/xhtml"));
+    assert_eq!(style.namespace_uri(Some("svg")), Some("svg2"));
+    assert_eq!(style.namespace_uri(Some("html")), None);
+}
+
+#[test]
+fn namespace_02() {
+    let (style, diagnostics) = StyleSheet::parse_with_diagnostics("\
+@namespace svg;
+@namespace svg url(a) b;
+svg|rect { fill:red }
+");
+    assert!(style.namespaces.is_empty());
+    assert_eq!(style.to_string(), "svg|rect { fill:red; }");
+    assert_eq!(diagnostics, vec![
+        (TextPos::new(1, 1, 0), Error::InvalidValue(TextPos::new(1, 15, 14))),
+        (TextPos::new(2, 1, 16), Error::InvalidByte { expected: b';', actual: b'b', pos: TextPos::new(2, 23, 38) }),
+    ]);
+}
+
+#[test]
+fn retain_supported_pseudo_elements() {
+    let mut style = StyleSheet::parse("
+        p::selection { color:blue }
+        p::before { content:'a' }
+        p { color:red }
+        p:after { content:'b' }
+    ");
+    style.retain_supported_pseudo_elements(&["before", "after"]);
+    assert_eq!(style.to_string(), "p { color:red; }\np::before { content:'a'; }\np::after { content:'b'; }");
+
+    style.retain_supported_pseudo_elements(&[]);
+    assert_eq!(style.to_string(), "p { color:red; }");
+}
+
+#[test]
+fn property_01() {
+    let style = StyleSheet::parse("\
+@property --main-color {
+    syntax: '<color>';
+    inherits: false;
+    initial-value: red;
+}
+@property --any { syntax: \"*\"; inherits: TRUE }
+a { color: var(--main-color) }");
+    assert_eq!(style.properties, vec![
+        Property { name: "--main-color", syntax: "<color>", inherits: false, initial_value: Some("red") },
+        Property { name: "--any", syntax: "*", inherits: true, initial_value: None },
+    ]);
+    assert_eq!(style.properties[0].initial_value.map(Color::parse), Some(Some(Color::new_rgba(255, 0, 0, 255))));
+    assert_eq!(style.to_string(), "@property --main-color { syntax:\"<color>\";inherits:false;initial-value:red; }
+@property --any { syntax:\"*\";inherits:true; }
+a { color:var(--main-color); }");
+}
+
+#[test]
+fn property_02() {
+    let (style, diagnostics) = StyleSheet::parse_with_diagnostics("\
+@property --a { syntax: '<color>'; inherits: false }
+@property --b { syntax: <color>; inherits: false; initial-value: red }
+@property --c { syntax: '<color>'; inherits: maybe; initial-value: red }
+@property d { syntax: '*'; inherits: false }
+a { color:red }");
+    assert!(style.properties.is_empty());
+    assert_eq!(style.to_string(), "a { color:red; }");
+    assert_eq!(diagnostics, vec![
+        (TextPos::new(1, 1, 0), Error::InvalidValue(TextPos::new(1, 1, 0))),
+        (TextPos::new(2, 17, 69), Error::InvalidValue(TextPos::new(2, 25, 77))),
+        (TextPos::new(2, 1, 53), Error::InvalidValue(TextPos::new(2, 1, 53))),
+        (TextPos::new(3, 1, 124), Error::InvalidValue(TextPos::new(3, 1, 124))),
+        (TextPos::new(4, 1, 197), Error::InvalidIdent(TextPos::new(4, 11, 207))),
+    ]);
+}
+
+#[test]
+fn font_face_01() {
+    let style = StyleSheet::parse("\
+@font-face {
+    font-family: 'Open Sans';
+    src: url(/fonts/OpenSans.woff2) format(\"woff2\"), local('Open Sans');
+    font-weight: 400;
+}
+@font-face { font-family: Other }
+a { font-family: 'Open Sans' }");
+    assert_eq!(style.font_faces, vec![
+        FontFace { declarations: vec![
+            Declaration::new("font-family", "'Open Sans'", false),
+            Declaration::new("src", "url(/fonts/OpenSans.woff2) format(\"woff2\"), local('Open Sans')", false),
+            Declaration::new("font-weight", "400", false),
+        ] },
+        FontFace { declarations: vec![Declaration::new("font-family", "Other", false)] },
+    ]);
+    assert_eq!(style.to_string(), "@font-face { font-family:'Open Sans';\
+src:url(/fonts/OpenSans.woff2) format(\"woff2\"), local('Open Sans');font-weight:400; }
+@font-face { font-family:Other; }
+a { font-family:'Open Sans'; }");
+}
+
+#[test]
+fn font_face_02() {
+    let (style, diagnostics) = StyleSheet::parse_with_diagnostics("@font-face a { src: url(a.woff) } b { color:red }");
+    assert!(style.font_faces.is_empty());
+    assert_eq!(style.to_string(), "b { color:red; }");
+    assert_eq!(diagnostics, vec![
+        (TextPos::new(1, 1, 0), Error::InvalidByte { expected: b'{', actual: b'a', pos: TextPos::new(1, 12, 11) }),
+    ]);
+}
+
+#[test]
+fn keyframes_01() {
+    let style = StyleSheet::parse("\
+@keyframes mymove {
+    from { top: 0px }
+    50% { top: 50px; opacity: 0.5 }
+    TO, 75.5% { top: 100px }
+}
+@keyframes 'quoted' {}
+a { animation: mymove 5s }");
+    assert_eq!(style.keyframes, vec![
+        Keyframes {
+            name: "mymove",
+            frames: vec![
+                (KeyframeSelector::From, vec![Declaration::new("top", "0px", false)]),
+                (KeyframeSelector::Percent(50.0), vec![
+                    Declaration::new("top", "50px", false),
+                    Declaration::new("opacity", "0.5", false),
+                ]),
+                (KeyframeSelector::To, vec![Declaration::new("top", "100px", false)]),
+                (KeyframeSelector::Percent(75.5), vec![Declaration::new("top", "100px", false)]),
+            ],
+        },
+        Keyframes { name: "quoted", frames: vec![] },
+    ]);
+    assert_eq!(style.to_string(), "@keyframes mymove { from { top:0px; } 50% { top:50px;opacity:0.5; } \
+to { top:100px; } 75.5% { top:100px; } }
+@keyframes quoted { }
+a { animation:mymove 5s; }");
+}
+
+#[test]
+fn keyframes_02() {
+    let (style, diagnostics) = StyleSheet::parse_with_diagnostics("\
+@keyframes a {
+    middle { top: 0 }
+    150% { top: 1px }
+    to { top: 2px }
+    from
+}
+@keyframes { to { top: 0 } }
+b { color:red }");
+    assert_eq!(style.keyframes, vec![
+        Keyframes { name: "a", frames: vec![(KeyframeSelector::To, vec![Declaration::new("top", "2px", false)])] },
+    ]);
+    assert_eq!(style.to_string(), "@keyframes a { to { top:2px; } }\nb { color:red; }");
+    assert_eq!(diagnostics, vec![
+        (TextPos::new(2, 5, 19), Error::InvalidValue(TextPos::new(2, 5, 19))),
+        (TextPos::new(3, 5, 41), Error::InvalidValue(TextPos::new(3, 5, 41))),
+        (TextPos::new(5, 5, 83), Error::InvalidValue(TextPos::new(5, 5, 83))),
+        (TextPos::new(7, 1, 90), Error::InvalidIdent(TextPos::new(7, 12, 101))),
+    ]);
+}
+
+#[test]
+fn query_01() {
+    let style = StyleSheet::parse("\
+.btn-primary { color:red }
+a .btn-primary, .btn-primary:hover { color:green }
+.btn-primary::before { content:'' }
+div.btn-primary { color:blue }");
+    let selectors = |text| style.query(text).iter().map(|r| r.selector.to_string()).collect::<Vec<_>>();
+    assert_eq!(selectors(".btn-primary"), vec!["*[class~='btn-primary']"]);
+    assert_eq!(selectors("  a   .btn-primary  "), vec!["a *[class~='btn-primary']"]);
+    assert_eq!(selectors(".btn-primary::before"), vec!["*[class~='btn-primary']::before"]);
+    assert!(selectors("a > .btn-primary").is_empty());
+    assert!(selectors(".btn-primary:focus").is_empty());
+    assert!(selectors("a >").is_empty());
+}
+
+#[test]
+fn declarations_hash() {
+    use std::collections::HashMap;
+
+    let style = StyleSheet::parse("a { color:red; fill:red } b { color: red } c { color:red !important }");
+    let mut counts = HashMap::new();
+    for d in style.rules.iter().flat_map(|r| r.declarations.iter()) {
+        *counts.entry(d.clone()).or_insert(0) += 1;
+    }
+
+    // Raw values are different, but declarations are still equal.
+    assert_eq!(counts.len(), 3);
+    assert_eq!(counts[&Declaration::new("color", "red", false)], 2);
+    assert_eq!(counts[&Declaration::new("color", "red", true)], 1);
+    assert_eq!(counts[&Declaration::new("fill", "red", false)], 1);
+}
+
+#[test]
+fn parse_partial() {
+    let text = "a { color:red } /* ok */ b { color:green } /* unclosed c { color:blue }";
+    let (style, offset) = StyleSheet::parse_partial(text);
+    assert_eq!(style.to_string(), "a { color:red; }\nb { color:green; }");
+    assert_eq!(offset, 43);
+    assert!(text[offset..].starts_with("/* unclosed"));
+
+    let text = "a { color:red } } ]";
+    assert_eq!(StyleSheet::parse_partial(text).1, text.len());
+    assert_eq!(StyleSheet::parse_partial("").1, 0);
+}
+
+#[test]
+fn parse_more_count() {
+    let mut style = StyleSheet::new();
+    assert_eq!(style.parse_more("a { color:red } b {} c, d { color:green }"), 3);
+    assert_eq!(style.parse_more("@import 'a.css'; /* comment */"), 0);
+    assert_eq!(style.parse_more(""), 0);
+    assert_eq!(style.parse_more("} { ]"), 0);
+    assert_eq!(style.parse_more("e { color:blue }"), 1);
+    assert_eq!(style.rules.len(), 4);
+}
+
+#[test]
+fn source_order() {
+    let mut style = StyleSheet::parse("#a { color:red } b {} c, d { color:green }");
+    style.parse_more("e { color:blue } .f { color:black }");
+
+    let order: Vec<_> = style.rules.iter().map(|r| (r.selector.to_string(), r.source_order)).collect();
+    assert_eq!(order, vec![
+        ("c".to_string(), 1),
+        ("d".to_string(), 2),
+        ("e".to_string(), 3),
+        ("*[class~='f']".to_string(), 4),
+        ("*#a".to_string(), 0),
+    ]);
+
+    // Continues after the largest remaining value.
+    style.rules.retain(|r| r.source_order < 2);
+    style.parse_more("g { color:white }");
+    assert_eq!(style.rules.iter().map(|r| r.source_order).collect::<Vec<_>>(), vec![1, 2, 0]);
+}
+
+#[test]
+fn supports_01() {
+    let style = StyleSheet::parse("\
+a { display:block }
+@supports (display: grid) and (not (display: inline-grid)) {
+    a { display:grid }
+    @media print { b { color:red } }
+    @supports selector(:has(a)) {
+        b, c { color:green }
+    }
+    d { color:blue }
+}
+e { color:red }");
+    let rules: Vec<_> = style.rules.iter().map(|r| (r.selector.to_string(), r.supports.clone())).collect();
+    let cond = "(display: grid) and (not (display: inline-grid))";
+    assert_eq!(rules, vec![
+        ("a".to_string(), vec![]),
+        ("a".to_string(), vec![cond]),
+        ("b".to_string(), vec![cond, "selector(:has(a))"]),
+        ("c".to_string(), vec![cond, "selector(:has(a))"]),
+        ("d".to_string(), vec![cond]),
+        ("e".to_string(), vec![]),
+    ]);
+}
+
+#[test]
+fn supports_02() {
+    let style = StyleSheet::parse("@supports (display: grid) { a, b { display:grid } } c { color:red }");
+    assert_eq!(style.to_string(), "\
+@supports (display: grid) { a { display:grid; } }
+@supports (display: grid) { b { display:grid; } }
+c { color:red; }");
+    assert_eq!(style.to_minified_string(), "@supports (display: grid){a,b{display:grid}}c{color:red}");
+}
+
+#[test]
+fn supports_03() {
+    let (style, diagnostics) = StyleSheet::parse_with_diagnostics("@supports; @supports { a { color:red } } b { color:red }");
+    assert_eq!(style.to_string(), "b { color:red; }");
+    assert_eq!(diagnostics, vec![
+        (TextPos::new(1, 1, 0), Error::InvalidValue(TextPos::new(1, 10, 9))),
+        (TextPos::new(1, 12, 11), Error::InvalidValue(TextPos::new(1, 22, 21))),
+    ]);
+}
+
+#[test]
+fn supports_unclosed() {
+    let style = StyleSheet::parse("@supports (display: grid) { a { display:grid }");
+    assert_eq!(style.rules[0].supports, vec!["(display: grid)"]);
+}
+
+#[test]
+fn shared_declarations() {
+    let mut style = StyleSheet::parse("a, b { color:red } c { color:red }");
+    assert!(std::sync::Arc::ptr_eq(&style.rules[0].declarations, &style.rules[1].declarations));
+    assert!(!std::sync::Arc::ptr_eq(&style.rules[1].declarations, &style.rules[2].declarations));
+
+    // Copy-on-write.
+    std::sync::Arc::make_mut(&mut style.rules[0].declarations)[0].value = "green";
+    assert_eq!(style.to_string(), "a { color:green; }\nb { color:red; }\nc { color:red; }");
+}
+
+#[test]
+fn deduplicate_declarations_01() {
+    let mut style = StyleSheet::parse("\
+a { color:red; width:1px; color:green }
+b { color:red !important; color:green; fill:red !important; fill:blue !important }
+c { color:red }");
+    style.deduplicate_declarations();
+    assert_eq!(style.to_string(), "\
+a { width:1px;color:green; }
+b { color:red !important;fill:blue !important; }
+c { color:red; }");
+}
+
+#[test]
+fn deduplicate_declarations_02() {
+    let mut style = StyleSheet::parse("a, b { color:red; color:green } c { color:red }");
+    style.deduplicate_declarations();
+    assert_eq!(style.to_string(), "a { color:green; }\nb { color:green; }\nc { color:red; }");
+    assert!(std::sync::Arc::ptr_eq(&style.rules[0].declarations, &style.rules[1].declarations));
+}
+
+#[test]
+fn important_invalid() {
+    let (style, diagnostics) = StyleSheet::parse_with_diagnostics("p { color: red !foo; width: 5px }");
+    assert_eq!(style.to_string(), "p { width:5px; }");
+    assert_eq!(diagnostics, vec![(TextPos::new(1, 5, 4), Error::InvalidValue(TextPos::new(1, 16, 15)))]);
+
+    let style = StyleSheet::parse("p { color: red !foo; width: 5px; fill: red !; stroke: red ! important }");
+    assert_eq!(style.to_string(), "p { width:5px;stroke:red !important; }");
+}
+
+#[test]
+fn important_multiline() {
+    let style = StyleSheet::parse("a {\n  color: red\n  !\n  important\n  ;\n  width: 1px\n}");
+    assert_eq!(style.to_string(), "a { color:red !important;width:1px; }");
+}
+
+#[test]
+fn rules_with_property() {
+    let style = StyleSheet::parse("
+        a { color:red }
+        b { width:1px }
+        c, d { width:1px; color:green !important }
+        e { colors:blue; --color:red }
+    ");
+
+    let selectors: Vec<_> = style.rules_with_property("color").map(|r| r.selector.to_string()).collect();
+    assert_eq!(selectors, vec!["a", "c", "d"]);
+    assert_eq!(style.rules_with_property("--color").count(), 1);
+    assert_eq!(style.rules_with_property("display").count(), 0);
+
+    // Names are decoded.
+    let style = StyleSheet::parse(r"a { \63 olor:red } b { c\olor:green }");
+    assert_eq!(style.rules_with_property("color").count(), 2);
+    assert_eq!(style.to_string(), "a { color:red; }\nb { color:green; }");
+}
+
+#[test]
+fn bom() {
+    let style = StyleSheet::parse("\u{FEFF}p { color:red }");
+    assert_eq!(style.to_string(), "p { color:red; }");
+
+    let style = StyleSheet::parse("\u{FEFF}\n/* comment */ p { color:red }");
+    assert_eq!(style.to_string(), "p { color:red; }");
+
+    // Only a leading BOM is skipped.
+    let style = StyleSheet::parse("p { color:red }\u{FEFF}a { color:red }");
+    assert!(style.query("a").is_empty());
+}
+
+#[test]
+fn charset() {
+    let style = StyleSheet::parse("@charset \"UTF-8\";\np { color:red }");
+    assert_eq!(style.charset, Some("UTF-8"));
+    assert_eq!(style.to_string(), "@charset \"UTF-8\";\np { color:red; }");
+
+    let style = StyleSheet::parse("\u{FEFF}@charset 'iso-8859-15'; p { color:red }");
+    assert_eq!(style.charset, Some("iso-8859-15"));
+
+    let mut style = StyleSheet::parse("p { color:red }");
+    assert_eq!(style.charset, None);
+    style.parse_more("@charset \"UTF-8\"; a { color:red }");
+    assert_eq!(style.charset, Some("UTF-8"));
+    style.parse_more("@charset \"ascii\";");
+    assert_eq!(style.charset, Some("UTF-8"));
+}
+
+#[test]
+fn charset_invalid() {
+    for text in &[
+        "p { color:red }\n@charset \"UTF-8\";\na { color:red }",
+        " @charset \"UTF-8\";\na { color:red }",
+        "/**/@charset \"UTF-8\";\na { color:red }",
+    ] {
+        let (style, diagnostics) = StyleSheet::parse_with_diagnostics(text);
+        assert_eq!(style.charset, None);
+        assert_eq!(style.query("a").len(), 1);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].1, Error::UnsupportedAtRule);
+    }
+
+    let (style, diagnostics) = StyleSheet::parse_with_diagnostics("@charset UTF-8; a { color:red }");
+    assert_eq!(style.charset, None);
+    assert_eq!(style.query("a").len(), 1);
+    assert_eq!(diagnostics, vec![(TextPos::new(1, 1, 0), Error::InvalidValue(TextPos::new(1, 10, 9)))]);
+}
+
+#[test]
+fn form_feed() {
+    let style = StyleSheet::parse("\x0Cp\x0C>\x0Ca\x0C{\x0Ccolor\x0C:\x0Cred\x0C}\x0C");
+    assert_eq!(style.to_string(), "p > a { color:red; }");
+}
+
+#[test]
+fn collection() {
+    let style = StyleSheet::parse("@import 'a.css'; a { color:red } b, c { color:green }");
+    assert_eq!(style.len(), 3);
+    assert!(!style.is_empty());
+    assert!(StyleSheet::parse("@import 'a.css';").is_empty());
+
+    let selectors: Vec<_> = (&style).into_iter().map(|r| r.selector.to_string()).collect();
+    assert_eq!(selectors, vec!["a", "b", "c"]);
+
+    let mut count = 0;
+    for rule in &style {
+        assert_eq!(rule.declarations.len(), 1);
+        count += 1;
+    }
+    assert_eq!(count, 3);
+
+    let rules: Vec<Rule> = style.into_iter().collect();
+    assert_eq!(rules.len(), 3);
+}
+
+#[test]
+fn nesting_limit() {
+    let options = ParseOptions { max_nesting_depth: 2, ..ParseOptions::default() };
+    let style = StyleSheet::parse_with_options("\
+        @supports (a) { @supports (b) { a { color:red } } }
+        @supports (a) { @supports (b) { @supports (c) { b { color:red } } } c { color:red } }
+        d:not(:is(.a)) { color:red }
+        e:is(:is(:is(.a))) { color:red }
+        f { color:red }
+    ", options);
+    assert_eq!(style.to_string(), "\
+        @supports (a) { @supports (b) { a { color:red; } } }\n\
+        @supports (a) { c { color:red; } }\n\
+        f { color:red; }\n\
+        d:not(*:is(*[class~='a'])) { color:red; }");
+
+    // Doesn't overflow the stack with default options.
+    let text = "@supports (a) {".repeat(10000) + "a { color:red }";
+    let (style, diagnostics) = StyleSheet::parse_with_diagnostics(&text);
+    assert!(style.rules.is_empty());
+    assert_eq!(diagnostics, vec![(TextPos::new(1, 961, 960), Error::NestingLimitExceeded)]);
+
+    let text = format!("a{}{} {{ color:red }}", ":is(".repeat(10000), ")".repeat(10000));
+    assert_eq!(Selector::try_parse(&text).unwrap_err().1, Error::NestingLimitExceeded);
+}
+
+#[test]
+fn input_size_limit() {
+    let text = "@import 'a.css'; a { color:red } b { color:green } c { color:blue }";
+    let options = ParseOptions { max_input_size: 50, ..ParseOptions::default() };
+    let style = StyleSheet::parse_with_options(text, options);
+    assert_eq!(style.to_string(), "@import \"a.css\";\na { color:red; }");
+
+    // A statement that crosses the limit is skipped as a whole.
+    let options = ParseOptions { max_input_size: 20, ..ParseOptions::default() };
+    let mut style = StyleSheet::parse_with_options(text, options);
+    assert_eq!(style.to_string(), "@import \"a.css\";");
+    assert_eq!(style.parse_more_with_options("@font-face { font-family:A } d { fill:red }", options), 0);
+    assert!(style.font_faces.is_empty());
+
+    // A cut comment is not an error.
+    let options = ParseOptions { max_input_size: 20, strict: true, ..ParseOptions::default() };
+    let style = StyleSheet::parse_with_options("a { color:red } /* comment */", options);
+    assert_eq!(style.to_string(), "a { color:red; }");
+
+    // A multibyte char is not split.
+    let options = ParseOptions { max_input_size: 3, ..ParseOptions::default() };
+    assert!(StyleSheet::parse_with_options("/*\u{20AC}*/", options).rules.is_empty());
+
+    assert!(!Error::InputSizeLimitExceeded.recoverable());
+}
+
+#[test]
+fn filter_rules() {
+    let mut style = StyleSheet::parse("@import 'a.css'; a { color:red } b, c { color:green } d { fill:red }");
+    let filtered = style.filter_rules(|rule| rule.declarations.iter().any(|d| d.name == "color"));
+    assert_eq!(filtered.to_string(), "@import \"a.css\";\na { color:red; }\nb { color:green; }\nc { color:green; }");
+    assert_eq!(filtered.imports.len(), 1);
+    assert!(std::sync::Arc::ptr_eq(&style.rules[0].declarations, &filtered.rules[0].declarations));
+    assert_eq!(style.len(), 4);
+
+    style.retain_rules(|rule| rule.selector.specificity() == Specificity([0, 0, 1]) && rule.source_order != 1);
+    assert_eq!(style.to_string(), "@import \"a.css\";\na { color:red; }\nc { color:green; }\nd { fill:red; }");
+}
+
+#[test]
+fn iter_declarations() {
+    let style = StyleSheet::parse("a { color:red } c, d { width:1px; color:green } e { }");
+    let pairs: Vec<_> = style.iter_declarations()
+        .map(|(s, d)| format!("{} {}:{}", s, d.name, d.value))
+        .collect();
+    assert_eq!(pairs, vec!["a color:red", "c width:1px", "c color:green", "d width:1px", "d color:green"]);
+    assert_eq!(StyleSheet::new().iter_declarations().count(), 0);
+}
+
+#[test]
+fn diagnostics_positions() {
+    fn naive_text_pos(text: &str, offset: usize) -> TextPos {
+        let row = text[..offset].matches('\n').count() + 1;
+        let col = text[..offset].chars().rev().take_while(|c| *c != '\n').count() + 1;
+        TextPos::new(row as u32, col as u32, offset)
+    }
+
+    let text = "\
+a { color:red }
+  b:unknown { color:green }
+
+  /* привет */ c & d { color:blue }
+p { color; color:green }
+\u{0444} > { color:red } @page { margin:1cm }
+e:unknown {}";
+    let (_, diagnostics) = StyleSheet::parse_with_diagnostics(text);
+    assert_eq!(diagnostics.len(), 6);
+    for (pos, _) in diagnostics {
+        assert_eq!(pos, naive_text_pos(text, pos.offset));
+    }
+}
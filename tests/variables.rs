@@ -0,0 +1,107 @@
+use std::collections::BTreeMap;
+
+use simplecss::*;
+
+fn resolve(text: &str, vars: &[(&'static str, &'static str)]) -> Vec<(String, String)> {
+    let vars: BTreeMap<_, _> = vars.iter().cloned().collect();
+    resolve_variables(&parse_inline_declarations(text), &vars)
+}
+
+fn pair(name: &str, value: &str) -> (String, String) {
+    (name.to_string(), value.to_string())
+}
+
+#[test]
+fn simple() {
+    let vars = [("--a", "red"), ("--b", "5px")];
+    assert_eq!(resolve("fill: var(--a); width: calc(var(--b) * 2); color: blue", &vars), vec![
+        pair("fill", "red"),
+        pair("width", "calc(5px * 2)"),
+        pair("color", "blue"),
+    ]);
+}
+
+#[test]
+fn fallback() {
+    let vars = [("--a", "red")];
+    assert_eq!(resolve("\
+        fill: var(--x, green);
+        stroke: var( --x , rgb(0, 0, 0) );
+        color: var(--x, var(--y, var(--a)));
+        margin: 1px var(--x,) 2px;
+        width: var(--a, blue)
+    ", &vars), vec![
+        pair("fill", "green"),
+        pair("stroke", "rgb(0, 0, 0)"),
+        pair("color", "red"),
+        pair("margin", "1px  2px"),
+        pair("width", "red"),
+    ]);
+}
+
+#[test]
+fn nested_variables() {
+    let vars = [("--a", "var(--b) solid"), ("--b", "1px")];
+    assert_eq!(resolve("border: var(--a) red", &vars), vec![pair("border", "1px solid red")]);
+}
+
+#[test]
+fn unresolved() {
+    let vars = [("--a", "var(--b)"), ("--b", "var(--a)")];
+    assert_eq!(resolve("\
+        fill: var(--x);
+        stroke: var(--a);
+        color: var(--a, green);
+        width: var(--x
+    ", &vars), vec![
+        pair("color", "green"),
+    ]);
+}
+
+#[test]
+fn cyclic_with_fallback() {
+    let vars = [("--a", "var(--a, x) var(--a, x)"), ("--b", "var(--c, y)"), ("--c", "var(--b)")];
+    assert_eq!(resolve("fill: var(--a); stroke: var(--b); color: var(--c, z); width: var(--a, 1)", &vars), vec![
+        pair("color", "z"),
+        pair("width", "1"),
+    ]);
+}
+
+#[test]
+fn exponential_expansion() {
+    // Each variable doubles the value, so `--v0` would be 2^40 times longer than `--v40`.
+    let names: Vec<String> = (0..=40).map(|i| format!("--v{}", i)).collect();
+    let values: Vec<String> = (0..40).map(|i| format!("var(--v{0}) var(--v{0})", i + 1)).collect();
+    let mut vars: BTreeMap<&str, &str> = names.iter().zip(&values).map(|(n, v)| (n.as_str(), v.as_str())).collect();
+    vars.insert("--v40", "a");
+
+    let declarations = parse_inline_declarations("fill: var(--v0); stroke: var(--v36)");
+    assert_eq!(resolve_variables(&declarations, &vars), vec![pair("stroke", "a a a a a a a a a a a a a a a a")]);
+}
+
+#[test]
+fn long_chain() {
+    let names: Vec<String> = (0..100_000).map(|i| format!("--v{}", i)).collect();
+    let values: Vec<String> = (0..100_000).map(|i| format!("var(--v{})", i + 1)).collect();
+    let mut vars: BTreeMap<&str, &str> = names.iter().zip(&values).map(|(n, v)| (n.as_str(), v.as_str())).collect();
+    vars.insert("--v100000", "red");
+
+    let declarations = parse_inline_declarations("fill: var(--v0); stroke: var(--v99990); color: var(--v1, blue)");
+    assert_eq!(resolve_variables(&declarations, &vars), vec![pair("stroke", "red"), pair("color", "blue")]);
+}
+
+#[test]
+fn not_a_variable() {
+    let vars = [("--a", "red")];
+    assert_eq!(resolve("\
+        content: 'var(--a)';
+        font-family: myvar(--a);
+        color: VAR(--a);
+        --b: var(--a) ф
+    ", &vars), vec![
+        pair("content", "'var(--a)'"),
+        pair("font-family", "myvar(--a)"),
+        pair("color", "red"),
+        pair("--b", "red ф"),
+    ]);
+}
@@ -1,3 +1,5 @@
+#![cfg(feature = "log")]
+
 fn run_process(input: &str) -> String {
     use std::io::Write;
     use std::process::Stdio;
@@ -24,6 +26,30 @@ fn style_01() {
 
 #[test]
 fn style_02() {
-    assert_eq!(run_process("@import 'subs.css';"),
-               "WARN: The @import rule is not supported. Skipped.\n");
+    assert_eq!(run_process("@page { margin: 1cm }"),
+               "WARN: The @page rule is not supported. Skipped.\n");
+}
+
+#[test]
+fn custom_handler() {
+    use std::sync::Mutex;
+
+    static WARNINGS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    fn handler(args: std::fmt::Arguments) {
+        WARNINGS.lock().unwrap().push(args.to_string());
+    }
+
+    let options = simplecss::ParseOptions {
+        warn_handler: handler,
+        ..simplecss::ParseOptions::default()
+    };
+
+    let style = simplecss::StyleSheet::parse_with_options("@page {} > {} a:unknown {} b { color:red }", options);
+    assert_eq!(style.to_string(), "b { color:red; }");
+    assert_eq!(*WARNINGS.lock().unwrap(), vec![
+        "The @page rule is not supported. Skipped.",
+        "Selector parsing failed cause unexpected combinator.",
+        "':unknown' is not supported. Selector skipped.",
+    ]);
 }